@@ -0,0 +1,76 @@
+use haversine_compute::{Pairs, Point};
+use instrument::cpu_timer::estimate_cpu_frequency;
+#[cfg(target_os = "linux")]
+use instrument::perf_counters::PerfCounters;
+use instrument::repetition::RepetitionTester;
+use json_parser::from_json::FromJson;
+use json_parser::parser::JsonParser;
+
+/// Compares deserializing pairs into the same types with json-parser and with `serde_json`, from
+/// a file generated with e.g. `haversine_input uniform 123 1000000`.
+///
+/// The file is read up front, so only parsing and deserializing are measured. On Linux, hardware
+/// events like instructions and cache misses are counted too where the kernel allows it.
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("Usage: deserialize_throughput <path to JSON file>");
+    let input = std::fs::read(&path).unwrap();
+    let input_size = input.len() as u64;
+    let cpu_frequency = estimate_cpu_frequency();
+
+    {
+        println!("json-parser: deserialize");
+        let mut repetition_tester = repetition_tester(input_size, cpu_frequency);
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            let pairs: Pairs = json_parser::de::from_slice(&input).unwrap();
+            repetition_tester.end();
+
+            assert!(!pairs.pairs.is_empty());
+            repetition_tester.count_bytes(input_size);
+        }
+    }
+
+    {
+        println!("json-parser: parse + FromJson");
+        let mut repetition_tester = repetition_tester(input_size, cpu_frequency);
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            let value = JsonParser::parse_from_bytes(&input).unwrap();
+            let pairs = Vec::<Point>::from_json(&value["pairs"]).unwrap();
+            repetition_tester.end();
+
+            assert!(!pairs.is_empty());
+            repetition_tester.count_bytes(input_size);
+        }
+    }
+
+    {
+        println!("serde_json: deserialize");
+        let mut repetition_tester = repetition_tester(input_size, cpu_frequency);
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            let pairs: Pairs = serde_json::from_slice(&input).unwrap();
+            repetition_tester.end();
+
+            assert!(!pairs.pairs.is_empty());
+            repetition_tester.count_bytes(input_size);
+        }
+    }
+}
+
+fn repetition_tester(input_size: u64, cpu_frequency: u64) -> RepetitionTester {
+    let repetition_tester = RepetitionTester::new(input_size, cpu_frequency, Some(10));
+
+    #[cfg(target_os = "linux")]
+    match PerfCounters::hardware_or_software() {
+        Ok(counters) => return repetition_tester.with_counters(counters),
+        Err(error) => eprintln!("Not counting events: {error}"),
+    }
+
+    repetition_tester
+}
@@ -1,6 +1,17 @@
-use serde::Serialize;
+use clap::ValueEnum;
+use json_parser::from_json::FromJson;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Serialize, Default)]
+/// How the pairs are laid out, in the files `haversine_input` writes and `compute` reads.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A single document, `{"pairs": [...]}`.
+    Json,
+    /// One pair per line.
+    Ndjson,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Default, FromJson)]
 pub struct Point {
     pub x0: f64,
     pub y0: f64,
@@ -8,6 +19,12 @@ pub struct Point {
     pub y1: f64,
 }
 
+/// The document `haversine_input` generates in JSON format.
+#[derive(Deserialize)]
+pub struct Pairs {
+    pub pairs: Vec<Point>,
+}
+
 #[must_use]
 pub fn compute_haversine(Point { x0, y0, x1, y1 }: Point, earth_radius: f64) -> f64 {
     let delta_latitude = y1 - y0;
@@ -1,11 +1,16 @@
 use assert_float_eq::{
     afe_abs, afe_absolute_error_msg, afe_is_absolute_eq, assert_float_absolute_eq,
 };
-use clap::Parser;
-use haversine_compute::{compute_haversine, Point};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use haversine_compute::{compute_haversine, Format, Pairs, Point};
+use json_parser::borrowed;
+use json_parser::events::EventReader;
+use json_parser::from_json::{FromJson, FromJsonError};
+use json_parser::map::Map;
+use json_parser::ndjson::NdjsonReader;
 use json_parser::parser::JsonParser;
-use json_parser::value::Value;
-use std::collections::HashMap;
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Read;
 use std::mem::size_of;
@@ -15,6 +20,21 @@ use std::mem::size_of;
 pub struct HaversineCompute {
     input: String,
     answers: Option<String>,
+    /// Read a single JSON document, or one pair per line.
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// Parse into borrowed values that point into the input buffer instead of owned copies.
+    #[arg(long)]
+    zero_copy: bool,
+    /// Read pairs one at a time straight from the file instead of parsing the whole document.
+    #[arg(long, conflicts_with = "zero_copy")]
+    stream: bool,
+    /// Deserialize the document straight into points with serde, without building a value first.
+    #[arg(long, conflicts_with_all = ["zero_copy", "stream"])]
+    serde: bool,
+    /// Map the file into memory and parse the mapping instead of reading it into a buffer first.
+    #[arg(long, conflicts_with_all = ["zero_copy", "stream", "serde"])]
+    mmap: bool,
 }
 
 #[cfg_attr(
@@ -34,23 +54,123 @@ fn read_json_file(mut file: File) -> Vec<u8> {
 }
 
 #[cfg_attr(feature = "profile", instrument)]
-fn parse_haversine_pairs(file: File) -> Vec<Value> {
+fn parse_haversine_pairs(file: File) -> Vec<Point> {
     let json_data = read_json_file(file);
     let json_value = JsonParser::parse_from_bytes(&json_data).unwrap();
 
     instrument_block!("Lookup & Convert", {
-        let points: &HashMap<String, Value> = (&json_value).try_into().unwrap();
-        let pairs: &Vec<Value> = points.get("pairs").unwrap().try_into().unwrap();
+        Vec::<Point>::from_json(&json_value["pairs"]).unwrap()
+    })
+}
+
+#[cfg_attr(feature = "profile", instrument)]
+fn parse_haversine_pairs_mmap(path: &str) -> Vec<Point> {
+    // SAFETY: The input file is generated ahead of time, and nothing writes to it while the
+    // answers are computed.
+    let json_value = unsafe { JsonParser::parse_mmap(path) }.unwrap();
+
+    instrument_block!("Lookup & Convert", {
+        Vec::<Point>::from_json(&json_value["pairs"]).unwrap()
+    })
+}
+
+/// Convert a borrowed pair, `{"x0": ..., "y0": ..., "x1": ..., "y1": ...}`, with the same checks
+/// and errors as [`Point::from_json`].
+fn point_from_borrowed(pair: &borrowed::Value) -> Result<Point, FromJsonError> {
+    let object: &Map<Cow<str>, borrowed::Value> = pair.try_into()?;
 
-        pairs.clone()
+    let coordinate = |name: &str| match object.get(name) {
+        Some(value) => {
+            f64::try_from(value).map_err(|error| FromJsonError::from(error).in_field(name))
+        }
+        None => Err(FromJsonError::missing_field(name)),
+    };
+
+    Ok(Point {
+        x0: coordinate("x0")?,
+        y0: coordinate("y0")?,
+        x1: coordinate("x1")?,
+        y1: coordinate("y1")?,
+    })
+}
+
+#[cfg_attr(feature = "profile", instrument)]
+fn parse_haversine_pairs_zero_copy(file: File) -> Vec<Point> {
+    let json_data = read_json_file(file);
+    let json_value = JsonParser::parse_borrowed(&json_data).unwrap();
+
+    instrument_block!("Lookup & Convert", {
+        let points: &Map<Cow<str>, borrowed::Value> = (&json_value).try_into().unwrap();
+        let pairs: &Vec<borrowed::Value> = points
+            .get("pairs")
+            .expect("Input has no pairs array")
+            .try_into()
+            .unwrap();
+
+        pairs
+            .iter()
+            .enumerate()
+            .map(|(index, pair)| {
+                point_from_borrowed(pair).map_err(|error| error.in_element(index).in_field("pairs"))
+            })
+            .collect::<Result<_, _>>()
+            .unwrap()
     })
 }
 
+#[cfg_attr(feature = "profile", instrument)]
+fn parse_haversine_pairs_streaming(file: File) -> Vec<Point> {
+    let mut events = EventReader::new(file);
+
+    let pairs = events
+        .elements("/pairs")
+        .unwrap()
+        .expect("Input has no pairs array")
+        .map(|pair| Point::from_json(&pair.unwrap()).unwrap())
+        .collect();
+
+    events.finish().unwrap();
+
+    pairs
+}
+
+#[cfg_attr(feature = "profile", instrument)]
+fn parse_haversine_pairs_serde(file: File) -> Vec<Point> {
+    let json_data = read_json_file(file);
+    let pairs: Pairs = json_parser::de::from_slice(&json_data).unwrap();
+
+    pairs.pairs
+}
+
+#[cfg_attr(feature = "profile", instrument)]
+fn parse_haversine_pairs_ndjson(file: File) -> Vec<Point> {
+    NdjsonReader::new(file)
+        .map(|pair| Point::from_json(&pair.unwrap()).unwrap())
+        .collect()
+}
+
 #[cfg_attr(feature = "profile", instrument(main))]
 fn main() {
-    let HaversineCompute { input, answers } = HaversineCompute::parse();
-
-    let file = File::open(input).unwrap();
+    let HaversineCompute {
+        input,
+        answers,
+        format,
+        zero_copy,
+        stream,
+        serde,
+        mmap,
+    } = HaversineCompute::parse();
+
+    if format == Format::Ndjson && (zero_copy || stream || serde || mmap) {
+        HaversineCompute::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--zero-copy, --stream, --serde and --mmap only apply to --format json",
+            )
+            .exit();
+    }
+
+    let file = File::open(&input).unwrap();
     let mut answers_file = answers.map(|answers| File::open(answers).unwrap());
 
     let mut answers = String::new();
@@ -65,7 +185,19 @@ fn main() {
         vec![]
     };
 
-    let pairs = parse_haversine_pairs(file);
+    let pairs = if format == Format::Ndjson {
+        parse_haversine_pairs_ndjson(file)
+    } else if zero_copy {
+        parse_haversine_pairs_zero_copy(file)
+    } else if stream {
+        parse_haversine_pairs_streaming(file)
+    } else if serde {
+        parse_haversine_pairs_serde(file)
+    } else if mmap {
+        parse_haversine_pairs_mmap(&input)
+    } else {
+        parse_haversine_pairs(file)
+    };
 
     let mut sum = 0.;
 
@@ -73,22 +205,15 @@ fn main() {
         "sum_pairs",
         {
             for (index, point) in pairs.iter().enumerate() {
-                if let Value::Object(object) = point {
-                    let x0: f64 = object.get("x0").unwrap().try_into().unwrap();
-                    let x1: f64 = object.get("x1").unwrap().try_into().unwrap();
-                    let y0: f64 = object.get("y0").unwrap().try_into().unwrap();
-                    let y1: f64 = object.get("y1").unwrap().try_into().unwrap();
-
-                    let result = compute_haversine(Point { x0, y0, x1, y1 }, 6372.8);
-                    sum += result;
-
-                    if let Some(answer) = answers.get(index) {
-                        assert_float_absolute_eq!(*answer, result, f64::EPSILON);
-                    }
+                let result = compute_haversine(*point, 6372.8);
+                sum += result;
+
+                if let Some(answer) = answers.get(index) {
+                    assert_float_absolute_eq!(*answer, result, f64::EPSILON);
                 }
             }
         },
-        (pairs.len() * size_of::<Value>()) as u64
+        (pairs.len() * size_of::<Point>()) as u64
     );
 
     println!("Average distance: {}", sum / pairs.len() as f64);
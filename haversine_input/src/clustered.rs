@@ -1,5 +1,5 @@
-use crate::types::{BoxDynError, HaversinePointGenerator, JsonResult};
-use haversine_compute::{compute_haversine, Point};
+use crate::types::{write_pairs, BoxDynError, HaversinePointGenerator};
+use haversine_compute::{compute_haversine, Format, Point};
 use rand::distributions::Distribution;
 use rand::distributions::Uniform;
 use rand::{thread_rng, Rng};
@@ -12,6 +12,7 @@ impl HaversinePointGenerator for ClusteredHaversinePointsGenerator {
         &self,
         seed: String,
         count: usize,
+        format: Format,
         output: &mut impl Write,
         results: &mut impl Write,
     ) -> Result<f64, BoxDynError> {
@@ -58,10 +59,7 @@ impl HaversinePointGenerator for ClusteredHaversinePointsGenerator {
             }
         }
 
-        let data = serde_json::to_vec(&JsonResult { pairs: container })?;
-
-        output.write_all(&data).unwrap();
-        output.flush().unwrap();
+        write_pairs(container, format, output)?;
 
         let computed_distances: Vec<f64> = container
             .iter()
@@ -1,7 +1,8 @@
 use crate::clustered::ClusteredHaversinePointsGenerator;
-use crate::types::HaversinePointGenerator;
+use crate::types::{output_path, HaversinePointGenerator};
 use crate::uniform::UniformHaversinePointsGenerator;
 use clap::{Parser, Subcommand};
+use haversine_compute::Format;
 use std::fs::File;
 
 mod clustered;
@@ -19,6 +20,9 @@ pub enum Method {
 pub struct HaversineInput {
     #[command(subcommand)]
     method: Method,
+    /// Write a single JSON document, or one pair per line.
+    #[arg(long, value_enum, global = true, default_value_t = Format::Json)]
+    format: Format,
 }
 
 fn main() {
@@ -29,11 +33,17 @@ fn main() {
     match &input.method {
         Method::Uniform { seed, points_count } => {
             let generator = UniformHaversinePointsGenerator {};
-            let mut output = File::create("./test.json").unwrap();
+            let mut output = File::create(output_path(input.format)).unwrap();
             let mut results = File::create("./results.dump").unwrap();
 
             let average_distance = generator
-                .generate(seed.clone(), *points_count, &mut output, &mut results)
+                .generate(
+                    seed.clone(),
+                    *points_count,
+                    input.format,
+                    &mut output,
+                    &mut results,
+                )
                 .unwrap();
 
             drop(output);
@@ -47,11 +57,17 @@ fn main() {
         }
         Method::Cluster { seed, points_count } => {
             let generator = ClusteredHaversinePointsGenerator {};
-            let mut output = File::create("./test.json").unwrap();
+            let mut output = File::create(output_path(input.format)).unwrap();
             let mut results = File::create("./results.dump").unwrap();
 
             let average_distance = generator
-                .generate(seed.clone(), *points_count, &mut output, &mut results)
+                .generate(
+                    seed.clone(),
+                    *points_count,
+                    input.format,
+                    &mut output,
+                    &mut results,
+                )
                 .unwrap();
 
             drop(output);
@@ -1,15 +1,70 @@
-use haversine_compute::Point;
+use haversine_compute::{Format, Point};
+use json_parser::value::{Number, Value};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
-use serde::Serialize;
 use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
 use std::hash::Hasher;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
 
-#[derive(Clone, Serialize, Default)]
-pub struct JsonResult<'a> {
-    pub pairs: &'a [Point],
+/// Path of the file the pairs are written to, when laid out as `format`.
+#[must_use]
+pub fn output_path(format: Format) -> &'static str {
+    match format {
+        Format::Json => "./test.json",
+        Format::Ndjson => "./test.ndjson",
+    }
+}
+
+/// Write a single pair, `{"x0": ..., "y0": ..., "x1": ..., "y1": ...}`, with its numbers
+/// formatted the way [`Value`] writes them.
+fn write_pair(writer: &mut impl Write, Point { x0, y0, x1, y1 }: &Point) -> io::Result<()> {
+    let number = |value: f64| Value::Number(Number::F64(value));
+
+    write!(
+        writer,
+        r#"{{"x0":{},"y0":{},"x1":{},"y1":{}}}"#,
+        number(*x0),
+        number(*y0),
+        number(*x1),
+        number(*y1)
+    )
+}
+
+/// Write `pairs` to `output`, laid out as `format`.
+pub fn write_pairs(
+    pairs: &[Point],
+    format: Format,
+    output: &mut impl Write,
+) -> Result<(), BoxDynError> {
+    let mut writer = BufWriter::new(output);
+
+    match format {
+        Format::Json => {
+            // Written pair by pair, without building the whole document in memory first.
+            writer.write_all(br#"{"pairs":["#)?;
+
+            for (index, pair) in pairs.iter().enumerate() {
+                if index > 0 {
+                    writer.write_all(b",")?;
+                }
+
+                write_pair(&mut writer, pair)?;
+            }
+
+            writer.write_all(b"]}")?;
+        }
+        Format::Ndjson => {
+            for pair in pairs {
+                write_pair(&mut writer, pair)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
 }
 
 pub type BoxDynError = Box<dyn Error>;
@@ -31,6 +86,7 @@ where
         &self,
         seed: String,
         count: usize,
+        format: Format,
         output: &mut impl Write,
         results: &mut impl Write,
     ) -> Result<f64, BoxDynError>;
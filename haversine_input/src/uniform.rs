@@ -1,5 +1,5 @@
-use crate::types::{BoxDynError, HaversinePointGenerator, JsonResult};
-use haversine_compute::{compute_haversine, Point};
+use crate::types::{write_pairs, BoxDynError, HaversinePointGenerator};
+use haversine_compute::{compute_haversine, Format, Point};
 use rand::distributions::{Distribution, Uniform};
 use std::io::Write;
 
@@ -10,6 +10,7 @@ impl HaversinePointGenerator for UniformHaversinePointsGenerator {
         &self,
         seed: String,
         count: usize,
+        format: Format,
         output: &mut impl Write,
         results: &mut impl Write,
     ) -> Result<f64, BoxDynError> {
@@ -43,10 +44,7 @@ impl HaversinePointGenerator for UniformHaversinePointsGenerator {
             computed_distances.push(compute_haversine(point, 6372.8));
         }
 
-        let data = serde_json::to_vec(&JsonResult { pairs: &container })?;
-
-        output.write_all(&data).unwrap();
-        output.flush().unwrap();
+        write_pairs(&container, format, output)?;
 
         let computed_distances: Vec<f64> = container
             .iter()
@@ -0,0 +1,19 @@
+//! Event counters that [`RepetitionTester`](crate::repetition::RepetitionTester) can read around
+//! every timed block, next to the CPU timer and page faults.
+
+use std::io;
+
+/// A fixed set of counters that are started and stopped together.
+///
+/// [`PerfCounters`](crate::perf_counters::PerfCounters) counts hardware and software events on
+/// Linux; anything else that can count around a block of code can implement this too.
+pub trait CounterSet {
+    /// Names of the counters, in the order [`CounterSet::stop`] returns their counts.
+    fn names(&self) -> Vec<&'static str>;
+
+    /// Reset all counters to zero and start counting.
+    fn start(&mut self) -> io::Result<()>;
+
+    /// Stop counting and return what each counter counted since [`CounterSet::start`].
+    fn stop(&mut self) -> io::Result<Vec<u64>>;
+}
@@ -1,13 +1,26 @@
+//! A cheap, high resolution timer for profiling, counting in ticks of the frequency returned by
+//! `estimate_cpu_frequency`.
+
 use crate::os_timer::{os_timer_frequency, read_os_timer};
 
+/// The time stamp counter, which ticks at a constant rate on any CPU from the last decade.
 #[cfg(target_arch = "x86_64")]
+#[must_use]
 pub fn read_cpu_timer() -> u64 {
-    unsafe {
-        _rdtsc();
-    }
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+/// Like `read_cpu_timer`, but only once every earlier instruction has executed, so the timed
+/// code can't spill past the end of a block.
+#[cfg(target_arch = "x86_64")]
+#[must_use]
+pub fn read_cpu_timer_serialized() -> u64 {
+    let mut processor_id = 0;
+
+    unsafe { core::arch::x86_64::__rdtscp(&raw mut processor_id) }
 }
 
-#[cfg(target_arch = "aarch64")]
+#[cfg(all(target_arch = "aarch64", target_os = "macos"))]
 #[must_use]
 pub fn read_cpu_timer() -> u64 {
     use mach2::mach_time::mach_absolute_time;
@@ -15,6 +28,33 @@ pub fn read_cpu_timer() -> u64 {
     unsafe { mach_absolute_time() }
 }
 
+/// The virtual counter of the generic timer, which Linux makes readable from user space.
+#[cfg(all(target_arch = "aarch64", not(target_os = "macos")))]
+#[must_use]
+pub fn read_cpu_timer() -> u64 {
+    let counter: u64;
+
+    unsafe {
+        core::arch::asm!("mrs {}, cntvct_el0", out(reg) counter, options(nomem, nostack));
+    }
+
+    counter
+}
+
+/// Without a cycle counter to read, fall back on the OS timer.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[must_use]
+pub fn read_cpu_timer() -> u64 {
+    read_os_timer()
+}
+
+/// On anything but x86_64, `read_cpu_timer` has no cheaper variant to fall back from.
+#[cfg(not(target_arch = "x86_64"))]
+#[must_use]
+pub fn read_cpu_timer_serialized() -> u64 {
+    read_cpu_timer()
+}
+
 #[must_use]
 pub fn estimate_cpu_frequency() -> u64 {
     let millis_to_wait = 100_u64;
@@ -36,11 +76,50 @@ pub fn estimate_cpu_frequency() -> u64 {
     let cpu_timer_end = read_cpu_timer();
     let cpu_timer_elapsed = cpu_timer_end - cpu_timer_start;
 
-    let mut cpu_frequency = 0;
+    (os_timer_frequency * cpu_timer_elapsed)
+        .checked_div(os_timer_elapsed)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn cpu_timer_is_monotonic() {
+        let first = read_cpu_timer();
+        let second = read_cpu_timer_serialized();
+        let third = read_cpu_timer();
+
+        assert!(first <= second && second <= third);
+    }
+
+    #[test]
+    fn estimated_frequency_is_plausible() {
+        let frequency = estimate_cpu_frequency();
 
-    if os_timer_elapsed > 0 {
-        cpu_frequency = os_timer_frequency * cpu_timer_elapsed / os_timer_elapsed;
+        // From the 24 MHz counters of ARM machines to the fastest x86 time stamp counters.
+        assert!(
+            (1_000_000..10_000_000_000).contains(&frequency),
+            "Estimated {frequency} Hz"
+        );
     }
 
-    cpu_frequency
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn estimated_frequency_matches_wall_clock() {
+        let frequency = estimate_cpu_frequency();
+
+        let start = read_cpu_timer();
+        let started_at = Instant::now();
+        std::thread::sleep(Duration::from_millis(50));
+        let elapsed = (read_cpu_timer() - start) as f64 / frequency as f64;
+        let expected = started_at.elapsed().as_secs_f64();
+
+        assert!(
+            (elapsed - expected).abs() < expected * 0.1,
+            "Measured {elapsed}s with the CPU timer, {expected}s with Instant"
+        );
+    }
 }
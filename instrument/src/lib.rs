@@ -1,6 +1,13 @@
+pub mod counters;
 pub mod cpu_timer;
 pub mod os_timer;
 pub mod page_faults;
+#[cfg(target_os = "linux")]
+pub mod perf_counters;
 pub mod profiler;
 pub mod repetition;
 pub mod stats;
+
+// Lets functions instrumented in this crate's tests refer to it by name.
+#[cfg(test)]
+extern crate self as instrument;
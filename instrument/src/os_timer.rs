@@ -1,5 +1,10 @@
+//! A monotonic OS clock, used to calibrate the CPU timer. `read_os_timer` returns ticks of
+//! `os_timer_frequency` per second.
+
+#[cfg(target_os = "macos")]
 use mach2::mach_time::{mach_absolute_time, mach_timebase_info};
 
+#[cfg(target_os = "macos")]
 #[must_use]
 #[allow(clippy::cast_sign_loss)]
 #[allow(clippy::cast_possible_truncation)]
@@ -18,7 +23,64 @@ pub fn os_timer_frequency() -> u64 {
     result as u64
 }
 
+#[cfg(target_os = "macos")]
 #[must_use]
 pub fn read_os_timer() -> u64 {
     unsafe { mach_absolute_time() }
 }
+
+/// `clock_gettime` counts in nanoseconds.
+#[cfg(not(target_os = "macos"))]
+#[must_use]
+pub fn os_timer_frequency() -> u64 {
+    1_000_000_000
+}
+
+/// The raw monotonic clock isn't slewed by NTP, so its rate stays constant while estimating the
+/// CPU frequency.
+#[cfg(target_os = "linux")]
+#[must_use]
+pub fn read_os_timer() -> u64 {
+    read_clock(libc::CLOCK_MONOTONIC_RAW)
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "linux"))))]
+#[must_use]
+pub fn read_os_timer() -> u64 {
+    read_clock(libc::CLOCK_MONOTONIC)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[allow(clippy::cast_sign_loss)]
+fn read_clock(clock: libc::clockid_t) -> u64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+
+    let result = unsafe { libc::clock_gettime(clock, &raw mut time) };
+    assert_eq!(result, 0, "clock_gettime failed");
+
+    time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn os_timer_follows_wall_clock() {
+        let start = read_os_timer();
+        std::thread::sleep(Duration::from_millis(20));
+        let elapsed = read_os_timer() - start;
+
+        // Sleeping takes at least as long as asked, and shouldn't take much longer.
+        let frequency = os_timer_frequency();
+        assert!(
+            elapsed >= frequency / 50,
+            "{elapsed} ticks at {frequency} Hz"
+        );
+        assert!(elapsed < frequency, "{elapsed} ticks at {frequency} Hz");
+    }
+}
@@ -9,24 +9,25 @@ fn main() {
 
     println!("Page Count,Touch Count,Fault Count,Extra Count");
 
-    let mut memory = memmap::MmapMut::map_anon(usize::try_from(memory_size).unwrap()).unwrap();
-
     for i in 0..page_count {
         let touch_count = i + 1;
         let touch_size = page_size * touch_count;
 
+        // Fresh memory every time, as pages only fault on their first touch.
+        let mut memory = memmap::MmapMut::map_anon(usize::try_from(memory_size).unwrap()).unwrap();
+
         let start_fault_count = get_absolute_page_faults_count().unwrap();
 
-        for index in 0..=touch_size {
+        for index in 0..touch_size {
             memory[index as usize] = index as u8;
         }
 
         let end_fault_count = get_absolute_page_faults_count().unwrap();
-        let fault_count = end_fault_count - start_fault_count;
+        let fault_count = (end_fault_count - start_fault_count).total();
 
         println!(
             "{page_count},{touch_count},{fault_count},{}",
-            fault_count - touch_count
+            i128::from(fault_count) - i128::from(touch_count)
         );
     }
 }
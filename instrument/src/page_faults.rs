@@ -1,55 +1,94 @@
+//! Page fault counts and the page size, as reported by the kernel.
+
 use std::io;
-use std::process::Command;
-use std::str::from_utf8;
-
-pub fn get_absolute_page_faults_count() -> Result<u64, io::Error> {
-    let pid = std::process::id();
-    let output = Command::new("top")
-        .arg("-e")
-        .arg("-ncols")
-        .arg("1")
-        .arg("-stats")
-        .arg("faults")
-        .arg("-l")
-        .arg("1")
-        .arg("-pid")
-        .arg(format!("{pid}"))
-        .output()?
-        .stdout;
-
-    let output_string =
-        from_utf8(&output).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
-
-    let output_vec = output_string
-        .lines()
-        .skip(11)
-        .take(2)
-        .collect::<Vec<&str>>();
-
-    if let [key, value] = output_vec.as_slice() {
-        if key.trim() == "FAULTS" {
-            return value
-                .trim()
-                .parse()
-                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error));
-        }
+use std::mem::MaybeUninit;
+use std::ops::Sub;
+
+/// Page faults of the current process.
+///
+/// Minor faults are served without I/O, like the first touch of freshly allocated memory, while
+/// major faults have to wait for it, like reading a page of a mapped file from disk.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PageFaults {
+    pub minor: u64,
+    pub major: u64,
+}
+
+impl PageFaults {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.minor + self.major
     }
+}
 
-    Err(io::Error::from(io::ErrorKind::InvalidInput))
+/// The faults that happened between two counts.
+impl Sub for PageFaults {
+    type Output = PageFaults;
+
+    fn sub(self, earlier: PageFaults) -> PageFaults {
+        PageFaults {
+            minor: self.minor - earlier.minor,
+            major: self.major - earlier.major,
+        }
+    }
 }
 
-pub static mut PAGE_SIZE: u64 = 0;
+/// Page faults of the whole process since it started, from `getrusage`, which is cheap enough to
+/// call around every timed block.
+pub fn get_absolute_page_faults_count() -> Result<PageFaults, io::Error> {
+    let mut usage = MaybeUninit::<libc::rusage>::zeroed();
+
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let usage = unsafe { usage.assume_init() };
+    let count = |count: libc::c_long| {
+        u64::try_from(count).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    };
 
+    Ok(PageFaults {
+        minor: count(usage.ru_minflt)?,
+        major: count(usage.ru_majflt)?,
+    })
+}
+
+#[must_use]
 pub fn get_page_size() -> u64 {
-    unsafe {
-        if PAGE_SIZE != 0 {
-            return PAGE_SIZE;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+
+    u64::try_from(page_size).expect("sysconf(_SC_PAGESIZE) failed")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn page_size_is_plausible() {
+        let page_size = get_page_size();
+
+        assert!(
+            page_size.is_power_of_two() && page_size >= 4096,
+            "{page_size}"
+        );
+    }
+
+    #[test]
+    fn touching_fresh_memory_faults() {
+        let page_size = usize::try_from(get_page_size()).unwrap();
+        let page_count = 64;
+        let mut memory = memmap::MmapMut::map_anon(page_size * page_count).unwrap();
+
+        let start = get_absolute_page_faults_count().unwrap();
+
+        for page in 0..page_count {
+            memory[page * page_size] = 1;
         }
 
-        let output = Command::new("pagesize").output().unwrap().stdout;
-        let output_str = from_utf8(&output).unwrap().trim();
-        PAGE_SIZE = output_str.parse().unwrap();
+        let faults = get_absolute_page_faults_count().unwrap() - start;
 
-        PAGE_SIZE
+        // Other tests running at the same time can only add to the count.
+        assert!(faults.minor >= page_count as u64, "{faults:?}");
     }
 }
@@ -0,0 +1,375 @@
+//! Hardware and software event counters from Linux's `perf_event_open`.
+//!
+//! All events of a [`PerfCounters`] are opened as one group, so they are enabled, disabled and
+//! read together with a single call each, and always cover exactly the same instructions.
+
+use crate::counters::CounterSet;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+const PERF_TYPE_SOFTWARE: u32 = 1;
+const PERF_TYPE_HW_CACHE: u32 = 3;
+
+const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+const PERF_COUNT_HW_CACHE_REFERENCES: u64 = 2;
+const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+const PERF_COUNT_HW_BRANCH_INSTRUCTIONS: u64 = 4;
+const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+
+const PERF_COUNT_HW_CACHE_L1D: u64 = 0;
+const PERF_COUNT_HW_CACHE_LL: u64 = 2;
+const PERF_COUNT_HW_CACHE_OP_READ: u64 = 0;
+const PERF_COUNT_HW_CACHE_RESULT_MISS: u64 = 1;
+
+const PERF_COUNT_SW_TASK_CLOCK: u64 = 1;
+const PERF_COUNT_SW_PAGE_FAULTS: u64 = 2;
+const PERF_COUNT_SW_CONTEXT_SWITCHES: u64 = 3;
+const PERF_COUNT_SW_CPU_MIGRATIONS: u64 = 4;
+
+const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+const PERF_FORMAT_GROUP: u64 = 1 << 3;
+
+const ATTR_FLAG_DISABLED: u64 = 1 << 0;
+const ATTR_FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+const ATTR_FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+const PERF_EVENT_IOC_ENABLE: libc::Ioctl = 0x2400;
+const PERF_EVENT_IOC_DISABLE: libc::Ioctl = 0x2401;
+const PERF_EVENT_IOC_RESET: libc::Ioctl = 0x2403;
+const PERF_IOC_FLAG_GROUP: libc::c_ulong = 1;
+
+/// The first version of `struct perf_event_attr`, which every kernel since 2.6.31 accepts, and
+/// which has all the fields needed for counting.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+/// An event the kernel can count, named like `perf stat` names it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerfEvent {
+    Cycles,
+    Instructions,
+    CacheReferences,
+    CacheMisses,
+    BranchInstructions,
+    BranchMisses,
+    L1dReadMisses,
+    LlcReadMisses,
+    /// Time spent on the CPU, in nanoseconds.
+    TaskClock,
+    PageFaults,
+    ContextSwitches,
+    CpuMigrations,
+}
+
+impl PerfEvent {
+    /// Counted by the CPU's performance monitoring unit.
+    pub const HARDWARE: [PerfEvent; 4] = [
+        PerfEvent::Cycles,
+        PerfEvent::Instructions,
+        PerfEvent::BranchMisses,
+        PerfEvent::L1dReadMisses,
+    ];
+
+    /// Counted by the kernel itself, so also available in virtual machines and containers without
+    /// access to the performance monitoring unit.
+    pub const SOFTWARE: [PerfEvent; 4] = [
+        PerfEvent::TaskClock,
+        PerfEvent::PageFaults,
+        PerfEvent::ContextSwitches,
+        PerfEvent::CpuMigrations,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            PerfEvent::Cycles => "cycles",
+            PerfEvent::Instructions => "instructions",
+            PerfEvent::CacheReferences => "cache-references",
+            PerfEvent::CacheMisses => "cache-misses",
+            PerfEvent::BranchInstructions => "branches",
+            PerfEvent::BranchMisses => "branch-misses",
+            PerfEvent::L1dReadMisses => "L1-dcache-load-misses",
+            PerfEvent::LlcReadMisses => "LLC-load-misses",
+            PerfEvent::TaskClock => "task-clock",
+            PerfEvent::PageFaults => "page-faults",
+            PerfEvent::ContextSwitches => "context-switches",
+            PerfEvent::CpuMigrations => "cpu-migrations",
+        }
+    }
+
+    fn type_and_config(self) -> (u32, u64) {
+        let cache_read_misses = |cache| {
+            cache | (PERF_COUNT_HW_CACHE_OP_READ << 8) | (PERF_COUNT_HW_CACHE_RESULT_MISS << 16)
+        };
+
+        match self {
+            PerfEvent::Cycles => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CPU_CYCLES),
+            PerfEvent::Instructions => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_INSTRUCTIONS),
+            PerfEvent::CacheReferences => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CACHE_REFERENCES),
+            PerfEvent::CacheMisses => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CACHE_MISSES),
+            PerfEvent::BranchInstructions => {
+                (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_INSTRUCTIONS)
+            }
+            PerfEvent::BranchMisses => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_MISSES),
+            PerfEvent::L1dReadMisses => (
+                PERF_TYPE_HW_CACHE,
+                cache_read_misses(PERF_COUNT_HW_CACHE_L1D),
+            ),
+            PerfEvent::LlcReadMisses => (
+                PERF_TYPE_HW_CACHE,
+                cache_read_misses(PERF_COUNT_HW_CACHE_LL),
+            ),
+            PerfEvent::TaskClock => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_TASK_CLOCK),
+            PerfEvent::PageFaults => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_PAGE_FAULTS),
+            PerfEvent::ContextSwitches => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_CONTEXT_SWITCHES),
+            PerfEvent::CpuMigrations => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_CPU_MIGRATIONS),
+        }
+    }
+}
+
+/// A group of events counted for the calling thread, in user space only.
+pub struct PerfCounters {
+    events: Vec<PerfEvent>,
+    /// The group leader comes first; all events are read through it.
+    files: Vec<File>,
+}
+
+impl PerfCounters {
+    /// Open a group counting `events`, stopped until [`CounterSet::start`].
+    ///
+    /// Fails if any of the events can't be counted, e.g. hardware events without access to the
+    /// performance monitoring unit, or with `perf_event_paranoid` set too high.
+    pub fn new(events: &[PerfEvent]) -> io::Result<Self> {
+        let mut files: Vec<File> = Vec::with_capacity(events.len());
+
+        for &event in events {
+            let leader = files.first().map_or(-1, AsRawFd::as_raw_fd);
+            files.push(open_event(event, leader)?);
+        }
+
+        Ok(Self {
+            events: events.to_vec(),
+            files,
+        })
+    }
+
+    /// [`PerfEvent::HARDWARE`] where the CPU's counters are accessible, otherwise
+    /// [`PerfEvent::SOFTWARE`].
+    pub fn hardware_or_software() -> io::Result<Self> {
+        Self::new(&PerfEvent::HARDWARE).or_else(|_| Self::new(&PerfEvent::SOFTWARE))
+    }
+
+    #[must_use]
+    pub fn events(&self) -> &[PerfEvent] {
+        &self.events
+    }
+
+    fn leader(&self) -> io::Result<&File> {
+        self.files
+            .first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no events to count"))
+    }
+
+    fn group_ioctl(&self, request: libc::Ioctl) -> io::Result<()> {
+        let leader = self.leader()?.as_raw_fd();
+
+        if unsafe { libc::ioctl(leader, request, PERF_IOC_FLAG_GROUP) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Counts of the whole group, scaled up if the kernel had to multiplex the group with other
+    /// events and it was only on the CPU for part of the time it was enabled.
+    fn read_group(&self) -> io::Result<Vec<u64>> {
+        // nr, time_enabled, time_running, then one value per event
+        let mut buffer = vec![0; (3 + self.events.len()) * 8];
+        let bytes_read = self.leader()?.read(&mut buffer)?;
+
+        let values: Vec<u64> = buffer[..bytes_read]
+            .chunks_exact(8)
+            .map(|value| u64::from_ne_bytes(value.try_into().unwrap()))
+            .collect();
+
+        let [count, time_enabled, time_running, counts @ ..] = values.as_slice() else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "short read of the counter group",
+            ));
+        };
+
+        if usize::try_from(*count) != Ok(self.events.len()) || counts.len() != self.events.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("read {count} counters instead of {}", self.events.len()),
+            ));
+        }
+
+        Ok(counts
+            .iter()
+            .map(|&count| {
+                if *time_running == 0 || time_running == time_enabled {
+                    return count;
+                }
+
+                let scaled =
+                    u128::from(count) * u128::from(*time_enabled) / u128::from(*time_running);
+
+                u64::try_from(scaled).unwrap_or(u64::MAX)
+            })
+            .collect())
+    }
+}
+
+fn open_event(event: PerfEvent, leader: RawFd) -> io::Result<File> {
+    let (kind, config) = event.type_and_config();
+
+    let mut flags = ATTR_FLAG_EXCLUDE_KERNEL | ATTR_FLAG_EXCLUDE_HV;
+    if leader == -1 {
+        flags |= ATTR_FLAG_DISABLED;
+    }
+
+    let attr = PerfEventAttr {
+        kind,
+        size: u32::try_from(std::mem::size_of::<PerfEventAttr>()).unwrap(),
+        config,
+        read_format: PERF_FORMAT_GROUP
+            | PERF_FORMAT_TOTAL_TIME_ENABLED
+            | PERF_FORMAT_TOTAL_TIME_RUNNING,
+        flags,
+        ..PerfEventAttr::default()
+    };
+
+    // This thread, on any CPU.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_perf_event_open,
+            &raw const attr,
+            0,
+            -1,
+            leader,
+            PERF_FLAG_FD_CLOEXEC,
+        )
+    };
+
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let fd =
+        RawFd::try_from(fd).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+impl CounterSet for PerfCounters {
+    fn names(&self) -> Vec<&'static str> {
+        self.events.iter().map(|event| event.name()).collect()
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        self.group_ioctl(PERF_EVENT_IOC_RESET)?;
+        self.group_ioctl(PERF_EVENT_IOC_ENABLE)
+    }
+
+    fn stop(&mut self) -> io::Result<Vec<u64>> {
+        self.group_ioctl(PERF_EVENT_IOC_DISABLE)?;
+        self.read_group()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The counters `open` returns, or `None` if this process isn't allowed to count events at
+    /// all, like in containers whose seccomp profile blocks `perf_event_open`.
+    fn counters(open: impl FnOnce() -> io::Result<PerfCounters>) -> Option<PerfCounters> {
+        match open() {
+            Ok(counters) => Some(counters),
+            Err(error)
+                if matches!(
+                    error.raw_os_error(),
+                    Some(libc::EACCES | libc::EPERM | libc::ENOSYS)
+                ) =>
+            {
+                eprintln!("Not counting events: {error}");
+                None
+            }
+            Err(error) => panic!("Failed to open counters: {error}"),
+        }
+    }
+
+    #[test]
+    fn software_events_count_page_faults() {
+        let Some(mut counters) = counters(|| PerfCounters::new(&PerfEvent::SOFTWARE)) else {
+            return;
+        };
+        let page_size = usize::try_from(crate::page_faults::get_page_size()).unwrap();
+        let page_count = 64;
+        let mut memory = memmap::MmapMut::map_anon(page_size * page_count).unwrap();
+
+        counters.start().unwrap();
+        for page in 0..page_count {
+            memory[page * page_size] = 1;
+        }
+        let counts = counters.stop().unwrap();
+
+        assert_eq!(
+            counters.names(),
+            [
+                "task-clock",
+                "page-faults",
+                "context-switches",
+                "cpu-migrations"
+            ]
+        );
+        assert!(counts[0] > 0, "{counts:?}");
+        assert!(counts[1] >= page_count as u64, "{counts:?}");
+    }
+
+    #[test]
+    fn counts_reset_on_start() {
+        let Some(mut counters) = counters(PerfCounters::hardware_or_software) else {
+            return;
+        };
+
+        counters.start().unwrap();
+        let busy = (0..1_000_000u64).map(std::hint::black_box).sum::<u64>();
+        let first = counters.stop().unwrap();
+
+        counters.start().unwrap();
+        let second = counters.stop().unwrap();
+
+        assert!(busy > 0);
+        assert_eq!(first.len(), counters.events().len());
+        // Counting almost nothing has to count less than a million additions.
+        assert!(second[0] < first[0], "{first:?} {second:?}");
+    }
+
+    #[test]
+    fn empty_group_is_an_error() {
+        let mut counters = PerfCounters::new(&[]).unwrap();
+
+        assert!(counters.start().is_err());
+    }
+}
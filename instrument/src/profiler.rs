@@ -1,10 +1,42 @@
-use crate::cpu_timer::read_cpu_timer;
+use crate::cpu_timer::{estimate_cpu_frequency, read_cpu_timer, read_cpu_timer_serialized};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
 
+/// Totals of every thread that has been profiled, and when profiling started and ended.
+///
+/// Each thread adds up its own entries without touching the others, and they are only merged
+/// when printing the results.
 pub struct GlobalProfiler {
     start: u64,
     end: Option<u64>,
-    children: Vec<ProfilerEntry>,
+    threads: Vec<Arc<Mutex<ThreadProfiler>>>,
+}
+
+/// Totals of a single thread, one per identifier, and the entries that haven't ended yet,
+/// innermost last.
+///
+/// Entries are added to their identifier's totals as they end, so the memory used only grows
+/// with the number of identifiers, not with the number of calls.
+struct ThreadProfiler {
+    name: String,
+    /// When profiling last started; entries that were already running are left out.
+    start: u64,
+    /// Totals in the order their identifiers were first hit.
+    anchors: Vec<ProfilerMetricEntry>,
+    positions: HashMap<&'static str, usize>,
+    open: Vec<OpenEntry>,
+}
+
+/// An entry that has started but not ended yet.
+struct OpenEntry {
+    anchor: usize,
+    start: u64,
+    ancestors: usize,
+    /// Inclusive time of the anchor when this entry started. The entry's own time is added to
+    /// it when it ends, replacing what any recursive calls inside it added in the meantime.
+    inclusive_before: u64,
+    children_elapsed: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -16,142 +48,262 @@ pub enum ProfilerEntry {
 #[derive(Debug, Clone)]
 pub struct ProfilerEntryData {
     identifier: &'static str,
-    start: u64,
-    end: Option<u64>,
+    /// Position of the entry in the stack of open entries, once it's pushed.
     index: usize,
-    parent_index: Option<usize>,
-    ancestors: usize,
-    children_elapsed: u64,
     processed_bytes: u64,
 }
 
+#[derive(Debug, Copy, Clone)]
 pub struct ProfilerMetricEntry {
     identifier: &'static str,
     elapsed_inclusive: u64,
     elapsed_exclusive: u64,
     hit_count: u64,
     ancestors_count: usize,
-    insert_index: usize,
     processed_bytes: u64,
 }
 
-pub struct GlobalProfilerWrapper(pub GlobalProfiler);
+/// Metrics of one thread, in the order their identifiers were first hit.
+struct ThreadMetrics {
+    name: String,
+    metrics: Vec<ProfilerMetricEntry>,
+}
+
+pub struct GlobalProfilerWrapper;
 
-pub static mut GLOBAL_PROFILER: GlobalProfilerWrapper = GlobalProfilerWrapper(GlobalProfiler {
+static GLOBAL_PROFILER: Mutex<GlobalProfiler> = Mutex::new(GlobalProfiler {
     start: 0,
     end: None,
-    children: Vec::new(),
+    threads: Vec::new(),
 });
 
-pub static mut LAST_INDEX: Vec<usize> = vec![];
+thread_local! {
+    static THREAD_PROFILER: Arc<Mutex<ThreadProfiler>> = GlobalProfilerWrapper::register_thread();
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().expect("Profiler lock poisoned")
+}
+
+fn with_thread_profiler<R>(f: impl FnOnce(&mut ThreadProfiler) -> R) -> R {
+    THREAD_PROFILER.with(|profiler| f(&mut lock(profiler)))
+}
 
 impl GlobalProfilerWrapper {
     pub fn start() {
-        let profiler = unsafe { &mut GLOBAL_PROFILER.0 };
+        let mut profiler = lock(&GLOBAL_PROFILER);
+
+        // Threads that have exited since the last run only hold on to their old totals.
+        profiler
+            .threads
+            .retain(|thread| Arc::strong_count(thread) > 1);
+
+        let start = read_cpu_timer();
 
-        profiler.children = Vec::with_capacity(2048);
-        profiler.start = read_cpu_timer();
+        // Open entries keep their place in the stack, so that they can still end, but they
+        // started before `start` and aren't added to the new totals.
+        for thread in &profiler.threads {
+            let mut thread = lock(thread);
+
+            thread.start = start;
+            for anchor in &mut thread.anchors {
+                *anchor = ProfilerMetricEntry::new(anchor.identifier);
+            }
+        }
+
+        profiler.end = None;
+        profiler.start = start;
     }
 
     pub fn end() {
-        let profiler = unsafe { &mut GLOBAL_PROFILER.0 };
-
-        profiler.end = Some(read_cpu_timer());
+        lock(&GLOBAL_PROFILER).end = Some(read_cpu_timer());
 
         GlobalProfilerWrapper::print_results();
     }
 
-    pub fn push(entry: &mut ProfilerEntry) {
-        let profiler = unsafe { &mut GLOBAL_PROFILER.0 };
+    fn register_thread() -> Arc<Mutex<ThreadProfiler>> {
+        let current = thread::current();
+        let name = current
+            .name()
+            .map_or_else(|| format!("{:?}", current.id()), ToString::to_string);
+
+        let mut global = lock(&GLOBAL_PROFILER);
+
+        let profiler = Arc::new(Mutex::new(ThreadProfiler {
+            name,
+            start: global.start,
+            anchors: Vec::new(),
+            positions: HashMap::new(),
+            open: Vec::new(),
+        }));
+
+        global.threads.push(Arc::clone(&profiler));
 
-        profiler.children.push(entry.clone());
+        profiler
+    }
+
+    pub fn push(entry: &mut ProfilerEntry) {
+        let entry_data = entry.inner_mut();
 
-        let index = profiler.children.len() - 1;
-        entry.inner_mut().index = index;
+        with_thread_profiler(|profiler| {
+            let anchor = profiler.anchor(entry_data.identifier);
 
-        unsafe { LAST_INDEX.push(index) }
+            entry_data.index = profiler.open.len();
+            profiler.open.push(OpenEntry {
+                anchor,
+                ancestors: profiler.open.len(),
+                inclusive_before: profiler.anchors[anchor].elapsed_inclusive,
+                children_elapsed: 0,
+                start: read_cpu_timer(),
+            });
+        });
     }
 
+    /// Print a breakdown for each thread that recorded entries, followed by one adding up all
+    /// threads if there was more than one.
+    ///
+    /// Percentages are relative to the time between `start` and `end` on the calling thread, so
+    /// the aggregate of threads running in parallel can add up to more than 100%.
     pub fn print_results() {
-        let profiler = unsafe { &GLOBAL_PROFILER.0 };
+        let profiler = lock(&GLOBAL_PROFILER);
 
         let start = profiler.start;
         let end = profiler
             .end
             .expect("Didn't finish profiling before trying to print results");
-        let children = &profiler.children;
+        let threads = profiler.thread_metrics();
+        drop(profiler);
 
         let total = end - start;
-        let ratio = 100.0 / total as f64;
-
-        let mut child_map = HashMap::<&str, ProfilerMetricEntry>::new();
-        let mut insert_index = 0;
-
-        for child in children {
-            let total_runtime = child.compute_runtime();
-            let children_runtime = child.get_child_elapsed();
-            let processed_bytes = child.processed_byes();
-
-            if let Some(child_entry) = child_map.get_mut(child.identifier()) {
-                child_entry.hit_count += 1;
-                child_entry.elapsed_inclusive += total_runtime;
-                child_entry.elapsed_exclusive += total_runtime - children_runtime;
-                child_entry.processed_bytes += processed_bytes;
-            } else {
-                child_map.insert(
-                    child.identifier(),
-                    ProfilerMetricEntry {
-                        identifier: child.identifier(),
-                        hit_count: 1,
-                        elapsed_inclusive: total_runtime,
-                        elapsed_exclusive: total_runtime - children_runtime,
-                        ancestors_count: child.inner().ancestors,
-                        insert_index,
-                        processed_bytes,
-                    },
-                );
-
-                insert_index += 1;
+        let cpu_frequency = estimate_cpu_frequency();
+
+        if let [thread] = threads.as_slice() {
+            print_metrics(&thread.metrics, total, cpu_frequency, "");
+        } else {
+            for thread in &threads {
+                println!("Thread {}:", thread.name);
+                print_metrics(&thread.metrics, total, cpu_frequency, "\t");
             }
+
+            println!("All threads:");
+            print_metrics(&aggregate(&threads), total, cpu_frequency, "\t");
         }
 
-        let mut entries = child_map.values().collect::<Vec<_>>();
-        entries.sort_by(|a, b| a.insert_index.cmp(&b.insert_index));
-
-        for value in entries {
-            let tab = "\t";
-            let prefix = tab.repeat(value.ancestors_count);
-
-            let run_time = RunTime::new(value.elapsed_inclusive);
-            let percentage = ratio * value.elapsed_exclusive as f64;
-
-            if value.elapsed_exclusive.abs_diff(value.elapsed_inclusive) < 100 {
-                println!(
-                    "{prefix}{}[{}] took {run_time} ({percentage:.4}%)",
-                    value.identifier, value.hit_count
-                );
-            } else {
-                let percentage_with_children = ratio * value.elapsed_inclusive as f64;
-
-                println!(
-                    "{prefix}{}[{}] took {run_time} ({percentage:.4}% | {percentage_with_children:.4}% w/ children)",
-                    value.identifier,
-                    value.hit_count
-                );
-            }
+        let program_runtime = RunTime::with_timer_frequency(total, cpu_frequency);
+        println!("program took {program_runtime} ({total} cycles)");
+    }
+}
+
+impl GlobalProfiler {
+    /// Metrics of every thread with finished entries, in the order the threads were first
+    /// profiled. Entries still running, or that started before profiling did, are left out.
+    fn thread_metrics(&self) -> Vec<ThreadMetrics> {
+        self.threads
+            .iter()
+            .filter_map(|thread| {
+                let thread = lock(thread);
+                let metrics = thread
+                    .anchors
+                    .iter()
+                    .filter(|anchor| anchor.hit_count > 0)
+                    .copied()
+                    .collect::<Vec<_>>();
+
+                (!metrics.is_empty()).then(|| ThreadMetrics {
+                    name: thread.name.clone(),
+                    metrics,
+                })
+            })
+            .collect()
+    }
+}
 
-            let throughput = Throughput::new(value.processed_bytes, run_time);
+impl ThreadProfiler {
+    /// Index of the totals for `identifier`, adding them if it's hit for the first time.
+    fn anchor(&mut self, identifier: &'static str) -> usize {
+        *self.positions.entry(identifier).or_insert_with(|| {
+            self.anchors.push(ProfilerMetricEntry::new(identifier));
+            self.anchors.len() - 1
+        })
+    }
+}
 
-            if throughput.data_processed() > 0. {
-                println!(
-                    "{prefix}=> Processed {:.2} MB at {throughput}",
-                    throughput.data_processed()
-                );
-            }
+impl ProfilerMetricEntry {
+    fn new(identifier: &'static str) -> Self {
+        Self {
+            identifier,
+            elapsed_inclusive: 0,
+            elapsed_exclusive: 0,
+            hit_count: 0,
+            ancestors_count: 0,
+            processed_bytes: 0,
         }
+    }
+}
 
-        let program_runtime = RunTime::new(end - start);
-        println!("program took {program_runtime} ({} cycles)", end - start);
+/// Add `entry` to the metric with the same identifier, or append it if it's the first.
+fn add_metric(
+    metrics: &mut Vec<ProfilerMetricEntry>,
+    positions: &mut HashMap<&'static str, usize>,
+    entry: ProfilerMetricEntry,
+) {
+    if let Some(&position) = positions.get(entry.identifier) {
+        let metric = &mut metrics[position];
+
+        metric.hit_count += entry.hit_count;
+        metric.elapsed_inclusive += entry.elapsed_inclusive;
+        metric.elapsed_exclusive += entry.elapsed_exclusive;
+        metric.processed_bytes += entry.processed_bytes;
+    } else {
+        positions.insert(entry.identifier, metrics.len());
+        metrics.push(entry);
+    }
+}
+
+fn aggregate(threads: &[ThreadMetrics]) -> Vec<ProfilerMetricEntry> {
+    let mut metrics = Vec::new();
+    let mut positions = HashMap::new();
+
+    for entry in threads.iter().flat_map(|thread| &thread.metrics) {
+        add_metric(&mut metrics, &mut positions, *entry);
+    }
+
+    metrics
+}
+
+fn print_metrics(metrics: &[ProfilerMetricEntry], total: u64, cpu_frequency: u64, indent: &str) {
+    let ratio = 100.0 / total as f64;
+
+    for value in metrics {
+        let tab = "\t";
+        let prefix = format!("{indent}{}", tab.repeat(value.ancestors_count));
+
+        let run_time = RunTime::with_timer_frequency(value.elapsed_inclusive, cpu_frequency);
+        let percentage = ratio * value.elapsed_exclusive as f64;
+
+        if value.elapsed_exclusive.abs_diff(value.elapsed_inclusive) < 100 {
+            println!(
+                "{prefix}{}[{}] took {run_time} ({percentage:.4}%)",
+                value.identifier, value.hit_count
+            );
+        } else {
+            let percentage_with_children = ratio * value.elapsed_inclusive as f64;
+
+            println!(
+                "{prefix}{}[{}] took {run_time} ({percentage:.4}% | {percentage_with_children:.4}% w/ children)",
+                value.identifier,
+                value.hit_count
+            );
+        }
+
+        let throughput = Throughput::new(value.processed_bytes, run_time);
+
+        if throughput.data_processed() > 0. {
+            println!(
+                "{prefix}=> Processed {:.2} MB at {throughput}",
+                throughput.data_processed()
+            );
+        }
     }
 }
 
@@ -161,18 +313,7 @@ use ProfilerEntry::*;
 impl ProfilerEntry {
     #[must_use]
     pub fn identifier(&self) -> &'static str {
-        match self {
-            CodeBlock(ProfilerEntryData { identifier, .. })
-            | Function(ProfilerEntryData { identifier, .. }) => identifier,
-        }
-    }
-
-    #[must_use]
-    pub fn index(&self) -> usize {
-        match self {
-            CodeBlock(ProfilerEntryData { index, .. })
-            | Function(ProfilerEntryData { index, .. }) => *index,
-        }
+        self.inner().identifier
     }
 
     #[must_use]
@@ -188,100 +329,334 @@ impl ProfilerEntry {
         }
     }
 
+    /// Add the time since the entry was pushed to the totals of its identifier.
+    pub fn end(self) {
+        let end = read_cpu_timer_serialized();
+        let data = self.inner();
+
+        with_thread_profiler(|profiler| {
+            let entry = match profiler.open.pop() {
+                Some(entry) if profiler.open.len() == data.index => entry,
+                _ => panic!("Invalid entry: {data:?}"),
+            };
+            let elapsed = end - entry.start;
+
+            if let Some(parent) = profiler.open.last_mut() {
+                parent.children_elapsed += elapsed;
+            }
+
+            // It belongs to a previous run.
+            if entry.start < profiler.start {
+                return;
+            }
+
+            let anchor = &mut profiler.anchors[entry.anchor];
+
+            // Recursive calls end first, but are printed under the outermost one.
+            if anchor.hit_count == 0 || entry.ancestors < anchor.ancestors_count {
+                anchor.ancestors_count = entry.ancestors;
+            }
+
+            anchor.hit_count += 1;
+            anchor.elapsed_inclusive = entry.inclusive_before + elapsed;
+            anchor.elapsed_exclusive += elapsed - entry.children_elapsed;
+            anchor.processed_bytes += data.processed_bytes;
+        });
+    }
+}
+
+impl ProfilerEntryData {
     #[must_use]
-    pub fn parent(&self) -> Option<usize> {
-        match self {
-            CodeBlock(ProfilerEntryData { parent_index, .. })
-            | Function(ProfilerEntryData { parent_index, .. }) => *parent_index,
+    pub fn init(identifier: &'static str) -> Self {
+        Self {
+            identifier,
+            index: 0,
+            processed_bytes: 0,
         }
     }
 
-    pub fn end(self) {
-        match self {
-            Function(data) | CodeBlock(data) => {
-                let profiler = unsafe { &mut GLOBAL_PROFILER.0 };
+    #[must_use]
+    pub fn init_with_throughput(identifier: &'static str, processed_bytes: impl Into<u64>) -> Self {
+        Self {
+            processed_bytes: processed_bytes.into(),
+            ..Self::init(identifier)
+        }
+    }
+}
 
-                if let Some(entry) = profiler.children.get_mut(data.index) {
-                    let entry = entry.inner_mut();
-                    let end = read_cpu_timer();
-                    entry.end = Some(end);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use instrument_macros::instrument;
+    use std::sync::mpsc;
+    use std::sync::PoisonError;
 
-                    let elapsed = end - entry.start;
+    /// The tests share the global profiler, so they can't run at the same time.
+    static PROFILER_TESTS: Mutex<()> = Mutex::new(());
 
-                    unsafe {
-                        LAST_INDEX.pop();
-                    }
+    fn block(identifier: &'static str, inner: impl FnOnce()) {
+        let mut entry = CodeBlock(ProfilerEntryData::init(identifier));
+        GlobalProfilerWrapper::push(&mut entry);
 
-                    if let Some(parent_index) = entry.parent_index {
-                        if let Some(parent) = profiler.children.get_mut(parent_index) {
-                            parent.add_child_elapsed(elapsed);
-                        }
-                    }
-                } else {
-                    panic!("Invalid entry: {data:?}");
-                }
-            }
-        }
+        inner();
+
+        entry.end();
     }
 
-    pub fn add_child_elapsed(&mut self, elapsed: u64) {
-        match self {
-            CodeBlock(data) | Function(data) => data.children_elapsed += elapsed,
+    fn finish() -> Vec<ThreadMetrics> {
+        let mut profiler = lock(&GLOBAL_PROFILER);
+        profiler.end = Some(read_cpu_timer());
+
+        profiler.thread_metrics()
+    }
+
+    fn current_thread_metrics() -> Vec<ProfilerMetricEntry> {
+        finish()
+            .into_iter()
+            .find(|thread| Some(thread.name.as_str()) == thread::current().name())
+            .expect("Nothing profiled on this thread")
+            .metrics
+    }
+
+    #[instrument]
+    fn countdown(depth: u32) -> u32 {
+        if depth == 0 {
+            0
+        } else {
+            countdown(depth - 1) + 1
         }
     }
 
-    #[must_use]
-    pub fn get_child_elapsed(&self) -> u64 {
-        match self {
-            CodeBlock(data) | Function(data) => data.children_elapsed,
+    /// Recurse into each other like `process_object` and `process_array` do.
+    #[instrument]
+    fn even(depth: u32) -> bool {
+        if depth == 0 {
+            true
+        } else {
+            odd(depth - 1)
         }
     }
 
-    #[must_use]
-    pub fn compute_runtime(&self) -> u64 {
-        if let Some(end) = self.inner().end {
-            end - self.inner().start
+    #[instrument]
+    fn odd(depth: u32) -> bool {
+        if depth == 0 {
+            false
         } else {
-            panic!("Profiler ended but entry didn't finish: {self:?}");
+            even(depth - 1)
         }
     }
 
-    #[must_use]
-    pub fn processed_byes(&self) -> u64 {
-        self.inner().processed_bytes
+    fn metric(metrics: &[ProfilerMetricEntry], identifier: &str) -> ProfilerMetricEntry {
+        *metrics
+            .iter()
+            .find(|metric| metric.identifier == identifier)
+            .unwrap_or_else(|| panic!("No metric for {identifier}: {metrics:?}"))
     }
-}
 
-impl ProfilerEntryData {
-    #[must_use]
-    pub fn init(identifier: &'static str) -> Self {
-        unsafe {
-            Self {
-                identifier,
-                start: read_cpu_timer(),
-                end: None,
-                index: 0,
-                parent_index: LAST_INDEX.last().copied(),
-                ancestors: LAST_INDEX.len(),
-                children_elapsed: 0,
-                processed_bytes: 0,
+    #[test]
+    fn threads_are_profiled_separately() {
+        let _guard = PROFILER_TESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        GlobalProfilerWrapper::start();
+
+        thread::scope(|scope| {
+            for worker in 0..2 {
+                thread::Builder::new()
+                    .name(format!("worker-{worker}"))
+                    .spawn_scoped(scope, || {
+                        for _ in 0..3 {
+                            block("outer", || block("inner", || {}));
+                        }
+                    })
+                    .unwrap();
             }
+        });
+        block("test", || {});
+
+        let threads = finish();
+        let workers = threads
+            .iter()
+            .filter(|thread| thread.name.starts_with("worker-"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(workers.len(), 2);
+        for worker in workers {
+            let outer = metric(&worker.metrics, "outer");
+            let inner = metric(&worker.metrics, "inner");
+
+            assert_eq!((outer.hit_count, outer.ancestors_count), (3, 0));
+            assert_eq!((inner.hit_count, inner.ancestors_count), (3, 1));
+            assert_eq!(
+                outer.elapsed_exclusive,
+                outer.elapsed_inclusive - inner.elapsed_inclusive
+            );
         }
+
+        let current = threads
+            .iter()
+            .find(|thread| Some(thread.name.as_str()) == thread::current().name())
+            .unwrap();
+        assert_eq!(metric(&current.metrics, "test").hit_count, 1);
+
+        let all = aggregate(&threads);
+        assert_eq!(metric(&all, "outer").hit_count, 6);
+        assert_eq!(metric(&all, "inner").hit_count, 6);
     }
 
-    #[must_use]
-    pub fn init_with_throughput(identifier: &'static str, processed_bytes: impl Into<u64>) -> Self {
-        unsafe {
-            Self {
-                identifier,
-                start: read_cpu_timer(),
-                end: None,
-                index: 0,
-                parent_index: LAST_INDEX.last().copied(),
-                ancestors: LAST_INDEX.len(),
-                children_elapsed: 0,
-                processed_bytes: processed_bytes.into(),
+    #[test]
+    fn totals_dont_grow_with_calls() {
+        let _guard = PROFILER_TESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        GlobalProfilerWrapper::start();
+
+        let sizes = thread::spawn(|| {
+            for _ in 0..1000 {
+                block("repeated", || block("nested", || {}));
             }
+
+            with_thread_profiler(|profiler| (profiler.anchors.len(), profiler.open.len()))
+        })
+        .join()
+        .unwrap();
+
+        let all = aggregate(&finish());
+
+        assert_eq!(sizes, (2, 0));
+        assert_eq!(metric(&all, "repeated").hit_count, 1000);
+        assert_eq!(metric(&all, "nested").hit_count, 1000);
+    }
+
+    #[test]
+    fn running_entries_are_left_out() {
+        let _guard = PROFILER_TESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        GlobalProfilerWrapper::start();
+
+        let (started, wait_for_start) = mpsc::channel();
+        let (finish_block, wait_for_finish) = mpsc::channel::<()>();
+
+        let worker = thread::Builder::new()
+            .name("still-running".to_string())
+            .spawn(move || {
+                block("finished", || {});
+                block("running", || {
+                    started.send(()).unwrap();
+                    wait_for_finish.recv().unwrap();
+                });
+            })
+            .unwrap();
+
+        wait_for_start.recv().unwrap();
+        let threads = finish();
+        finish_block.send(()).unwrap();
+        worker.join().unwrap();
+
+        let worker = threads
+            .iter()
+            .find(|thread| thread.name == "still-running")
+            .unwrap();
+
+        assert_eq!(metric(&worker.metrics, "finished").hit_count, 1);
+        assert!(worker
+            .metrics
+            .iter()
+            .all(|metric| metric.identifier != "running"));
+    }
+
+    #[test]
+    fn recursion_counts_inclusive_time_once() {
+        let _guard = PROFILER_TESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        GlobalProfilerWrapper::start();
+
+        let mut wall_time = 0;
+        for depth in [10, 3] {
+            let start = read_cpu_timer();
+            assert_eq!(countdown(depth), depth);
+            wall_time += read_cpu_timer() - start;
         }
+
+        let countdown = metric(&current_thread_metrics(), "countdown");
+
+        assert_eq!(countdown.hit_count, 11 + 4);
+        assert!(countdown.elapsed_inclusive <= wall_time, "{countdown:?}");
+        // Every call's own time adds up to the outermost calls' time.
+        assert_eq!(countdown.elapsed_exclusive, countdown.elapsed_inclusive);
+    }
+
+    #[test]
+    fn mutual_recursion_counts_inclusive_time_once() {
+        let _guard = PROFILER_TESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        GlobalProfilerWrapper::start();
+
+        let start = read_cpu_timer();
+        assert!(!even(9));
+        let wall_time = read_cpu_timer() - start;
+
+        let metrics = current_thread_metrics();
+        let even = metric(&metrics, "even");
+        let odd = metric(&metrics, "odd");
+
+        assert_eq!((even.hit_count, odd.hit_count), (5, 5));
+        assert_eq!((even.ancestors_count, odd.ancestors_count), (0, 1));
+        assert!(even.elapsed_inclusive <= wall_time, "{even:?}");
+        // The outermost `odd` runs inside the outermost `even`.
+        assert!(
+            odd.elapsed_inclusive < even.elapsed_inclusive,
+            "{odd:?} {even:?}"
+        );
+        assert_eq!(
+            even.elapsed_exclusive + odd.elapsed_exclusive,
+            even.elapsed_inclusive
+        );
+    }
+
+    #[test]
+    fn starting_again_keeps_blocks_that_are_running() {
+        let _guard = PROFILER_TESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        GlobalProfilerWrapper::start();
+
+        let (started, wait_for_start) = mpsc::channel();
+        let (restarted, wait_for_restart) = mpsc::channel::<()>();
+
+        let worker = thread::Builder::new()
+            .name("restarted".to_string())
+            .spawn(move || {
+                block("before", || {
+                    block("finished before", || {});
+                    started.send(()).unwrap();
+                    wait_for_restart.recv().unwrap();
+                    block("inside", || {});
+                });
+                block("after", || {});
+            })
+            .unwrap();
+
+        wait_for_start.recv().unwrap();
+        GlobalProfilerWrapper::start();
+        restarted.send(()).unwrap();
+        worker.join().unwrap();
+
+        let threads = finish();
+        let worker = threads
+            .iter()
+            .find(|thread| thread.name == "restarted")
+            .unwrap();
+        let identifiers = worker
+            .metrics
+            .iter()
+            .map(|metric| metric.identifier)
+            .collect::<Vec<_>>();
+
+        assert_eq!(identifiers, ["inside", "after"]);
+        assert_eq!(metric(&worker.metrics, "inside").ancestors_count, 1);
     }
 }
@@ -1,5 +1,6 @@
-use crate::cpu_timer::read_cpu_timer;
-use crate::page_faults::{get_absolute_page_faults_count, get_page_size};
+use crate::counters::CounterSet;
+use crate::cpu_timer::{read_cpu_timer, read_cpu_timer_serialized};
+use crate::page_faults::{get_absolute_page_faults_count, get_page_size, PageFaults};
 use crate::stats::{RunTime, Throughput};
 use crossterm::terminal::ClearType;
 use crossterm::{cursor, terminal, QueueableCommand};
@@ -19,7 +20,9 @@ pub struct TestResult {
     pub total_time: u64,
     pub max_time: u64,
     pub min_time: u64,
-    pub page_faults: u64,
+    pub page_faults: PageFaults,
+    /// One entry per counter of the [`CounterSet`] given to [`RepetitionTester::with_counters`].
+    pub counters: Vec<CounterResult>,
 }
 
 impl Default for TestResult {
@@ -29,11 +32,36 @@ impl Default for TestResult {
             total_time: 0,
             max_time: 0,
             min_time: u64::MAX,
-            page_faults: 0,
+            page_faults: PageFaults::default(),
+            counters: Vec::new(),
         }
     }
 }
 
+/// What a counter counted over each test, independently of which test was the fastest.
+pub struct CounterResult {
+    pub name: &'static str,
+    pub min: u64,
+    pub max: u64,
+    pub total: u64,
+}
+
+impl CounterResult {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            min: u64::MAX,
+            max: 0,
+            total: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn average(&self, test_count: u64) -> u64 {
+        self.total.checked_div(test_count).unwrap_or(0)
+    }
+}
+
 pub struct RepetitionTester {
     target_byte_count: u64,
     cpu_timer_frequency: u64,
@@ -43,7 +71,10 @@ pub struct RepetitionTester {
     closed_block_count: u64,
     time_accumulated_this_test: i128,
     bytes_accumulated_this_test: u64,
-    faults_accumulated_this_test: i128,
+    faults_at_begin: PageFaults,
+    faults_accumulated_this_test: PageFaults,
+    counters: Option<Box<dyn CounterSet>>,
+    counts_accumulated_this_test: Vec<u64>,
     state: TestState,
     results: TestResult,
 }
@@ -64,12 +95,27 @@ impl RepetitionTester {
             closed_block_count: 0,
             time_accumulated_this_test: 0,
             bytes_accumulated_this_test: 0,
-            faults_accumulated_this_test: 0,
+            faults_at_begin: PageFaults::default(),
+            faults_accumulated_this_test: PageFaults::default(),
+            counters: None,
+            counts_accumulated_this_test: Vec::new(),
             state: TestState::Testing,
             results: TestResult::default(),
         }
     }
 
+    /// Also count the events of `counters` in every timed block, and report them next to the
+    /// timings.
+    #[must_use]
+    pub fn with_counters(mut self, counters: impl CounterSet + 'static) -> Self {
+        let names = counters.names();
+
+        self.counts_accumulated_this_test = vec![0; names.len()];
+        self.results.counters = names.into_iter().map(CounterResult::new).collect();
+        self.counters = Some(Box::new(counters));
+        self
+    }
+
     pub fn new_wave(
         &mut self,
         target_byte_count: u64,
@@ -89,20 +135,35 @@ impl RepetitionTester {
         self.tests_started_at = read_cpu_timer();
     }
 
+    /// Page faults are read outermost and the timer innermost, with the counters in between, so
+    /// that reading one of them stays out of what the ones inside it measure.
     pub fn begin(&mut self) {
         self.open_block_count += 1;
-        self.time_accumulated_this_test -= i128::from(read_cpu_timer());
+        self.faults_at_begin = get_absolute_page_faults_count().unwrap();
+
+        if let Some(counters) = &mut self.counters {
+            counters.start().expect("Failed to start counters");
+        }
 
-        let page_faults = get_absolute_page_faults_count().unwrap();
-        self.faults_accumulated_this_test -= i128::from(page_faults);
+        self.time_accumulated_this_test -= i128::from(read_cpu_timer());
     }
 
     pub fn end(&mut self) {
+        // Serialized, so the end of the block isn't read before its last instructions finish.
+        self.time_accumulated_this_test += i128::from(read_cpu_timer_serialized());
         self.closed_block_count += 1;
-        self.time_accumulated_this_test += i128::from(read_cpu_timer());
 
-        let page_faults = get_absolute_page_faults_count().unwrap();
-        self.faults_accumulated_this_test += i128::from(page_faults);
+        if let Some(counters) = &mut self.counters {
+            let counts = counters.stop().expect("Failed to read counters");
+
+            for (accumulated, count) in self.counts_accumulated_this_test.iter_mut().zip(counts) {
+                *accumulated += count;
+            }
+        }
+
+        let faults = get_absolute_page_faults_count().unwrap() - self.faults_at_begin;
+        self.faults_accumulated_this_test.minor += faults.minor;
+        self.faults_accumulated_this_test.major += faults.major;
     }
 
     pub fn count_bytes(&mut self, bytes: u64) {
@@ -138,7 +199,7 @@ impl RepetitionTester {
 
                 // first iteration
                 if self.open_block_count == 1 {
-                    results.page_faults = u64::try_from(self.faults_accumulated_this_test).unwrap();
+                    results.page_faults = self.faults_accumulated_this_test;
                 }
 
                 let elapsed =
@@ -147,6 +208,16 @@ impl RepetitionTester {
                 results.total_time += elapsed;
                 results.max_time = results.max_time.max(elapsed);
 
+                for (counter, &count) in results
+                    .counters
+                    .iter_mut()
+                    .zip(&self.counts_accumulated_this_test)
+                {
+                    counter.min = counter.min.min(count);
+                    counter.max = counter.max.max(count);
+                    counter.total += count;
+                }
+
                 if results.min_time > elapsed {
                     results.min_time = elapsed;
                     self.tests_started_at = current_time;
@@ -171,7 +242,8 @@ impl RepetitionTester {
         self.closed_block_count = 0;
         self.time_accumulated_this_test = 0;
         self.bytes_accumulated_this_test = 0;
-        self.faults_accumulated_this_test = 0;
+        self.faults_accumulated_this_test = PageFaults::default();
+        self.counts_accumulated_this_test.fill(0);
     }
 
     fn print_new_stats(&self) {
@@ -211,7 +283,8 @@ impl RepetitionTester {
         let max_throughput = Throughput::new(self.target_byte_count, min_run_time);
         let min_throughput = Throughput::new(self.target_byte_count, max_run_time);
         let average_throughput = Throughput::new(self.target_byte_count, average_run_time);
-        let page_faults = self.results.page_faults;
+        let PageFaults { minor, major } = self.results.page_faults;
+        let page_faults = self.results.page_faults.total();
         let page_size = get_page_size();
 
         let page_fault_memory = (page_size * page_faults) as f64 / 1024. / 1024.;
@@ -219,7 +292,56 @@ impl RepetitionTester {
         println!("Min: {min_run_time} at {max_throughput}");
         println!("Max: {max_run_time} at {min_throughput}");
         println!("Avg: {average_run_time} at {average_throughput}");
-        println!("Page faults: {page_faults} ({page_fault_memory:.2}MB)");
+        println!(
+            "Page faults: {page_faults} ({page_fault_memory:.2}MB), {minor} minor, {major} major"
+        );
+
+        self.print_counters();
+    }
+
+    fn print_counters(&self) {
+        let counters = &self.results.counters;
+        if counters.is_empty() {
+            return;
+        }
+
+        let widths: Vec<usize> = counters
+            .iter()
+            .map(|counter| counter.name.len().max(counter.max.to_string().len()))
+            .collect();
+
+        print!("   ");
+        for (counter, width) in counters.iter().zip(&widths) {
+            print!("  {:>width$}", counter.name);
+        }
+        println!();
+
+        let test_count = self.results.test_count;
+        let rows = [
+            (
+                "Min",
+                counters
+                    .iter()
+                    .map(|counter| counter.min)
+                    .collect::<Vec<_>>(),
+            ),
+            ("Max", counters.iter().map(|counter| counter.max).collect()),
+            (
+                "Avg",
+                counters
+                    .iter()
+                    .map(|counter| counter.average(test_count))
+                    .collect(),
+            ),
+        ];
+
+        for (label, values) in rows {
+            print!("{label}");
+            for (value, width) in values.iter().zip(&widths) {
+                print!("  {value:>width$}");
+            }
+            println!();
+        }
     }
 
     #[must_use]
@@ -227,3 +349,58 @@ impl RepetitionTester {
         &self.results
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu_timer::estimate_cpu_frequency;
+    use std::io;
+
+    /// Counts every start, so each block counts one more than the one before.
+    struct Starts(u64);
+
+    impl CounterSet for Starts {
+        fn names(&self) -> Vec<&'static str> {
+            vec!["starts", "ones"]
+        }
+
+        fn start(&mut self) -> io::Result<()> {
+            self.0 += 1;
+            Ok(())
+        }
+
+        fn stop(&mut self) -> io::Result<Vec<u64>> {
+            Ok(vec![self.0, 1])
+        }
+    }
+
+    #[test]
+    fn counters_are_summed_per_test() {
+        let mut repetition_tester =
+            RepetitionTester::new(2, estimate_cpu_frequency(), Some(1)).with_counters(Starts(0));
+
+        while repetition_tester.loop_test() {
+            for _ in 0..2 {
+                repetition_tester.begin();
+                repetition_tester.count_bytes(1);
+                repetition_tester.end();
+            }
+        }
+
+        let results = repetition_tester.results();
+        let [starts, ones] = results.counters.as_slice() else {
+            panic!("expected two counters");
+        };
+
+        assert!(results.test_count > 0);
+        // The first test counts 1 + 2, the last one (2n - 1) + 2n.
+        assert_eq!((starts.name, starts.min), ("starts", 3));
+        assert_eq!(starts.max, 4 * results.test_count - 1);
+        assert_eq!(
+            starts.total,
+            2 * results.test_count * results.test_count + results.test_count
+        );
+        assert_eq!((ones.name, ones.min, ones.max), ("ones", 2, 2));
+        assert_eq!(ones.average(results.test_count), 2);
+    }
+}
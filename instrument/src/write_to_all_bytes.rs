@@ -1,6 +1,3 @@
-use instrument::cpu_timer::estimate_cpu_frequency;
-use instrument::repetition::RepetitionTester;
-
 #[allow(clippy::cast_possible_truncation)]
 fn main() {
     let gigabyte = 1024 * 1024 * 1024;
@@ -0,0 +1,106 @@
+extern crate proc_macro;
+
+use darling::ast::Data;
+use darling::util::{Ignored, Override};
+use darling::{FromDeriveInput, FromField};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Generics, Ident, Path, Type};
+
+#[derive(FromDeriveInput)]
+#[darling(attributes(json), supports(struct_named))]
+struct FromJsonInput {
+    ident: Ident,
+    generics: Generics,
+    data: Data<Ignored, FromJsonField>,
+}
+
+#[derive(FromField)]
+#[darling(attributes(json))]
+struct FromJsonField {
+    ident: Option<Ident>,
+    ty: Type,
+    /// Name of the object member to read the field from, instead of the field's own.
+    rename: Option<String>,
+    /// Use `Default::default`, or the function at the given path, if the member is missing.
+    default: Option<Override<Path>>,
+}
+
+/// Implement `json_parser::from_json::FromJson` for a struct with named fields, reading each
+/// field from the object member of the same name.
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let FromJsonInput {
+        ident,
+        mut generics,
+        data,
+    } = match FromJsonInput::from_derive_input(&input) {
+        Ok(input) => input,
+        Err(error) => return error.write_errors().into(),
+    };
+
+    let fields = data
+        .take_struct()
+        .expect("only named structs are supported")
+        .fields;
+
+    let field_values = fields.iter().map(|field| {
+        let FromJsonField {
+            ident,
+            ty,
+            rename,
+            default,
+        } = field;
+
+        let name = rename
+            .clone()
+            .or_else(|| ident.as_ref().map(ToString::to_string));
+
+        let value = match default {
+            None => quote! {
+                ::json_parser::from_json::field::<#ty>(object, #name)?
+            },
+            Some(Override::Inherit) => quote! {
+                ::json_parser::from_json::field_or_else::<#ty>(
+                    object,
+                    #name,
+                    ::std::default::Default::default,
+                )?
+            },
+            Some(Override::Explicit(default)) => quote! {
+                ::json_parser::from_json::field_or_else::<#ty>(object, #name, #default)?
+            },
+        };
+
+        quote!(#ident: #value)
+    });
+
+    for parameter in generics.type_params_mut() {
+        parameter
+            .bounds
+            .push(parse_quote!(::json_parser::from_json::FromJson));
+    }
+
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    quote!(
+        impl #impl_generics ::json_parser::from_json::FromJson for #ident #type_generics #where_clause {
+            fn from_json(
+                value: &::json_parser::value::Value,
+            ) -> ::std::result::Result<Self, ::json_parser::from_json::FromJsonError> {
+                let ::json_parser::value::Value::Object(object) = value else {
+                    return ::std::result::Result::Err(
+                        ::json_parser::from_json::FromJsonError::expected("an object", value),
+                    );
+                };
+
+                ::std::result::Result::Ok(Self {
+                    #(#field_values,)*
+                })
+            }
+        }
+    )
+    .into()
+}
@@ -0,0 +1,25 @@
+#![no_main]
+
+use json_parser::borrowed;
+use json_parser::parser::JsonParser;
+use libfuzzer_sys::fuzz_target;
+
+// Every entry point should give the same value or the same error, whatever the input, and
+// anything that parses should survive being written and parsed again.
+fuzz_target!(|input: &[u8]| {
+    let result = JsonParser::parse_from_bytes(input);
+
+    assert_eq!(JsonParser::parse(input), result);
+    assert_eq!(
+        JsonParser::parse_borrowed(input).map(borrowed::Value::into_owned),
+        result
+    );
+
+    if let Ok(value) = result {
+        let output = value.to_string();
+        let reparsed = JsonParser::parse_from_bytes(output.as_bytes()).unwrap();
+
+        // Only numbers are allowed to change, as floats beyond `f64` are written as `null`.
+        assert_eq!(reparsed.to_string(), output);
+    }
+});
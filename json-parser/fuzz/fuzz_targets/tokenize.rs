@@ -0,0 +1,8 @@
+#![no_main]
+
+use json_parser::tokens::JsonTokenizer;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &[u8]| {
+    let _ = JsonTokenizer::<&[u8]>::from_bytes(input).tokenize_json();
+});
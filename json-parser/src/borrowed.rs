@@ -0,0 +1,238 @@
+use crate::map::Map;
+use crate::parser::BuildValue;
+use crate::value::{self, ConversionError, Number};
+use std::borrow::Cow;
+
+/// A JSON value that borrows its strings from the input data wherever possible.
+///
+/// Keys and strings without escape sequences point into the input buffer, so the only
+/// allocations made while parsing are for arrays, objects and strings that needed unescaping.
+/// Use [`Value::into_owned`] to detach it from the input.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value<'a> {
+    String(Cow<'a, str>),
+    Number(Number),
+    Boolean(bool),
+    Array(Vec<Value<'a>>),
+    Object(Map<Cow<'a, str>, Value<'a>>),
+    Null,
+}
+
+impl Value<'_> {
+    /// Convert into an owned [`value::Value`], copying any borrowed strings.
+    #[must_use]
+    pub fn into_owned(self) -> value::Value {
+        match self {
+            Value::String(string) => value::Value::String(string.into_owned()),
+            Value::Number(number) => value::Value::Number(number),
+            Value::Boolean(boolean) => value::Value::Boolean(boolean),
+            Value::Array(array) => {
+                value::Value::Array(array.into_iter().map(Value::into_owned).collect())
+            }
+            Value::Object(object) => {
+                let mut owned = Map::with_capacity(object.len());
+
+                // Append rather than collect to keep any duplicate keys.
+                for (key, value) in object {
+                    owned.append(key.into_owned(), value.into_owned());
+                }
+
+                value::Value::Object(owned)
+            }
+            Value::Null => value::Value::Null,
+        }
+    }
+
+    /// The JSON type of the value, as used in error messages.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Boolean(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+}
+
+impl From<Value<'_>> for value::Value {
+    fn from(value: Value<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+impl<'a> BuildValue<'a> for Value<'a> {
+    type Key = Cow<'a, str>;
+
+    fn key(key: Cow<'a, str>) -> Cow<'a, str> {
+        key
+    }
+
+    fn from_string(string: Cow<'a, str>) -> Self {
+        Value::String(string)
+    }
+
+    fn from_number(number: Number) -> Self {
+        Value::Number(number)
+    }
+
+    fn from_boolean(boolean: bool) -> Self {
+        Value::Boolean(boolean)
+    }
+
+    fn null() -> Self {
+        Value::Null
+    }
+
+    fn from_array(array: Vec<Self>) -> Self {
+        Value::Array(array)
+    }
+
+    fn from_object(object: Map<Cow<'a, str>, Value<'a>>) -> Self {
+        Value::Object(object)
+    }
+}
+
+impl<'a> TryFrom<&Value<'a>> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: &Value<'a>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Number(number) => number.try_into(),
+            _ => Err(ConversionError::wrong_type("f64", value.type_name())),
+        }
+    }
+}
+
+impl<'a, 'b> TryFrom<&'b Value<'a>> for &'b str {
+    type Error = ConversionError;
+
+    fn try_from(value: &'b Value<'a>) -> Result<Self, ConversionError> {
+        match value {
+            Value::String(value) => Ok(value),
+            _ => Err(ConversionError::wrong_type("&str", value.type_name())),
+        }
+    }
+}
+
+impl<'a> TryFrom<&Value<'a>> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: &Value<'a>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Boolean(value) => Ok(*value),
+            _ => Err(ConversionError::wrong_type("bool", value.type_name())),
+        }
+    }
+}
+
+impl<'a, 'b> TryFrom<&'b Value<'a>> for &'b Vec<Value<'a>> {
+    type Error = ConversionError;
+
+    fn try_from(value: &'b Value<'a>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Array(value) => Ok(value),
+            _ => Err(ConversionError::wrong_type("an array", value.type_name())),
+        }
+    }
+}
+
+impl<'a, 'b> TryFrom<&'b Value<'a>> for &'b Map<Cow<'a, str>, Value<'a>> {
+    type Error = ConversionError;
+
+    fn try_from(value: &'b Value<'a>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Object(value) => Ok(value),
+            _ => Err(ConversionError::wrong_type("an object", value.type_name())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::parser::JsonParser;
+
+    #[test]
+    fn strings_borrow_from_input() {
+        let input = br#"{"plain": "value", "escaped": "a\nb"}"#;
+        let value = JsonParser::parse_borrowed(input).unwrap();
+
+        let Value::Object(object) = value else {
+            panic!("Expected an object, got {value:?}");
+        };
+
+        for key in object.keys() {
+            assert!(matches!(key, Cow::Borrowed(_)));
+        }
+
+        assert!(matches!(
+            object.get("plain"),
+            Some(Value::String(Cow::Borrowed("value")))
+        ));
+        assert_eq!(
+            object.get("escaped"),
+            Some(&Value::String(Cow::Owned("a\nb".to_string())))
+        );
+    }
+
+    #[test]
+    fn matches_owned_parser() {
+        let input = r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377,"x1":41.844453001935875,"y1":-78.10213222087448},{"x0":115.42029308864215,"y0":87.52060937339934,"x1":83.39640643072113,"y1":28.643090267505812},{"sample":"string \"sample\" \u00e9\ud834\udd1e","nullable":null,"flags":[true,false],"ࠄࠀ":"ࠆࠄ"}]}"#;
+
+        let borrowed = JsonParser::parse_borrowed(input.as_bytes()).unwrap();
+        let owned = JsonParser::parse_from_bytes(input.as_bytes()).unwrap();
+
+        assert_eq!(borrowed.into_owned(), owned);
+    }
+
+    #[test]
+    fn errors_are_positioned() {
+        let error = JsonParser::parse_borrowed(b"{\n  \"a\": [1, 2}\n}").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 14);
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 13);
+
+        let error = JsonParser::parse_borrowed("[\"ü\", ü]".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('ü'));
+        assert_eq!(error.offset(), 7);
+        assert_eq!(error.column(), 7);
+
+        let error = JsonParser::parse_borrowed(b"[1, 2").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 0);
+
+        let error = JsonParser::parse_borrowed(b"{\"a\":").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedEndOfInput);
+        assert_eq!(error.offset(), 5);
+
+        let error = JsonParser::parse_borrowed(b"[1] [2]").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(error.offset(), 4);
+
+        let error = JsonParser::parse_borrowed(b"[01]").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidNumber);
+        assert_eq!(error.offset(), 2);
+
+        let error = JsonParser::parse_borrowed(b"[nul]").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidLiteral);
+        assert_eq!(error.offset(), 4);
+
+        let error = JsonParser::parse_borrowed(br#"["\uD834x"]"#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+        assert_eq!(error.offset(), 2);
+    }
+
+    #[test]
+    fn invalid_utf8_in_strings() {
+        let error = JsonParser::parse_borrowed(b"[\"ab\xffc\"]").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUtf8);
+        assert_eq!(error.offset(), 4);
+
+        let error = JsonParser::parse_borrowed(b"[\"\\n\xc3\"]").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUtf8);
+    }
+}
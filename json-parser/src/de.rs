@@ -0,0 +1,910 @@
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::input::Input;
+use crate::map::Map;
+use crate::options::{DuplicateKeys, ParserOptions};
+use crate::parser::JsonParser;
+use crate::reader::JsonReader;
+use crate::scanner::Scanner;
+use crate::value::{ConversionError, Number, Value};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::io::Read;
+
+/// Deserialize a `T` from an in-memory JSON document.
+///
+/// The input is deserialized in a single pass, without building a [`Value`] first, and strings
+/// without escape sequences can be borrowed from it, as `&str` fields for example.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Point<'a> {
+///     name: &'a str,
+///     x0: f64,
+///     y0: f64,
+/// }
+///
+/// let points: Vec<Point> =
+///     json_parser::de::from_slice(br#"[{"name": "a", "x0": 1.5, "y0": -2}]"#).unwrap();
+///
+/// assert_eq!((points[0].name, points[0].x0, points[0].y0), ("a", 1.5, -2.0));
+/// ```
+pub fn from_slice<'de, T: Deserialize<'de>>(input: &'de [u8]) -> Result<T, ParseError> {
+    deserialize_slice(input, &ParserOptions::default())
+}
+
+/// Deserialize a `T` from an in-memory JSON document, like [`from_slice`].
+pub fn from_str<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, ParseError> {
+    from_slice(input.as_bytes())
+}
+
+/// Deserialize a `T` from any [`Read`], which is buffered internally.
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T, ParseError> {
+    deserialize_reader(reader, &ParserOptions::default())
+}
+
+pub(crate) fn deserialize_slice<'de, T: Deserialize<'de>>(
+    input: &'de [u8],
+    options: &ParserOptions,
+) -> Result<T, ParseError> {
+    if let Some(max_size) = options.max_document_size {
+        if input.len() > max_size {
+            return Err(Scanner::new(input).error_at(ErrorKind::DocumentTooLarge, max_size));
+        }
+    }
+
+    deserialize_document(Scanner::new(input), options)
+}
+
+pub(crate) fn deserialize_reader<R: Read, T: DeserializeOwned>(
+    reader: R,
+    options: &ParserOptions,
+) -> Result<T, ParseError> {
+    deserialize_document(
+        JsonReader::new(reader).with_max_size(options.max_document_size),
+        options,
+    )
+}
+
+/// Deserialize a complete document, rejecting anything but whitespace after the top-level value.
+fn deserialize_document<'de, I: Input<'de>, T: Deserialize<'de>>(
+    input: I,
+    options: &ParserOptions,
+) -> Result<T, ParseError> {
+    let mut deserializer = Deserializer {
+        input,
+        options,
+        depth: 0,
+    };
+
+    let start = deserializer.input.mark();
+    let value =
+        T::deserialize(&mut deserializer).map_err(|error| deserializer.locate(error, start))?;
+
+    if deserializer.peek()?.is_some() {
+        return Err(deserializer.input.error(ErrorKind::TrailingCharacters));
+    }
+
+    Ok(value)
+}
+
+/// Errors from `Deserialize` implementations, which don't know where in the input they happened,
+/// are on line 0 until the deserializer gives them a position.
+impl de::Error for ParseError {
+    fn custom<T: Display>(message: T) -> Self {
+        let position = Position {
+            offset: 0,
+            line: 0,
+            column: 0,
+        };
+
+        ParseError::new(ErrorKind::Custom(message.to_string()), position)
+    }
+}
+
+/// Feeds the input to `Deserialize` implementations, following the same grammar, options and
+/// limits as [`JsonParser`].
+///
+/// Only [`DuplicateKeys::Error`] applies, as what happens to other repeated keys is up to the type
+/// being deserialized.
+struct Deserializer<'o, I> {
+    input: I,
+    options: &'o ParserOptions,
+    /// Number of arrays and objects the value being deserialized is nested in.
+    depth: usize,
+}
+
+impl<'de, I: Input<'de>> Deserializer<'_, I> {
+    /// Skip whitespace, and comments if they are allowed, and look at the next byte.
+    fn peek(&mut self) -> Result<Option<u8>, ParseError> {
+        JsonParser::skip_whitespace(&mut self.input, self.options)?;
+        self.input.peek()
+    }
+
+    /// Give `error` the position of `mark` if it came from a `Deserialize` implementation and
+    /// doesn't have one yet.
+    fn locate(&self, error: ParseError, mark: I::Mark) -> ParseError {
+        if error.line() == 0 {
+            self.input.error_at(error.kind().clone(), mark)
+        } else {
+            error
+        }
+    }
+
+    fn parse_string(&mut self, quote: u8) -> Result<Cow<'de, str>, ParseError> {
+        JsonParser::parse_string(&mut self.input, self.options, quote)
+    }
+
+    /// Parse the key of a member of the object starting at `start`.
+    fn parse_key(&mut self, start: I::Mark) -> Result<Cow<'de, str>, ParseError> {
+        match self.peek()? {
+            Some(b'"') => self.parse_string(b'"'),
+            Some(b'\'') if self.options.allow_single_quotes => self.parse_string(b'\''),
+            None => Err(self.input.error_at(ErrorKind::UnbalancedBrackets, start)),
+            _ => Err(self.input.unexpected()),
+        }
+    }
+
+    /// Consume the colon after a key of the object starting at `start`.
+    fn parse_colon(&mut self, start: I::Mark) -> Result<(), ParseError> {
+        match self.peek()? {
+            Some(b':') => {
+                self.input.discard();
+                Ok(())
+            }
+            None => Err(self.input.error_at(ErrorKind::UnbalancedBrackets, start)),
+            _ => Err(self.input.unexpected()),
+        }
+    }
+
+    /// Move on to the element or member after the first `count` of the array or object starting
+    /// at `start` and closed by `bracket`, returning whether there is one.
+    ///
+    /// The closing bracket is left for [`Deserializer::leave`].
+    fn next_element(
+        &mut self,
+        count: usize,
+        bracket: u8,
+        start: I::Mark,
+    ) -> Result<bool, ParseError> {
+        let next = self.peek()?;
+
+        if next == Some(bracket) {
+            return Ok(false);
+        }
+
+        if count > 0 {
+            match next {
+                Some(b',') => {
+                    self.input.discard();
+
+                    if self.options.allow_trailing_commas && self.peek()? == Some(bracket) {
+                        return Ok(false);
+                    }
+                }
+                Some(b']' | b'}') => return Err(self.input.error(ErrorKind::UnbalancedBrackets)),
+                None => return Err(self.input.error_at(ErrorKind::UnbalancedBrackets, start)),
+                _ => return Err(self.input.unexpected()),
+            }
+        }
+
+        if self
+            .options
+            .max_elements
+            .is_some_and(|maximum| count >= maximum)
+        {
+            return Err(self.input.error(ErrorKind::TooManyElements));
+        }
+
+        Ok(true)
+    }
+
+    /// Consume the opening bracket of an array or object.
+    fn enter(&mut self) -> Result<(), ParseError> {
+        if self.depth >= self.options.max_depth {
+            return Err(self.input.error(ErrorKind::NestingTooDeep));
+        }
+
+        self.depth += 1;
+        self.input.discard();
+
+        Ok(())
+    }
+
+    /// Consume the closing `bracket` of the array or object starting at `start`, once the type
+    /// being deserialized has taken what it needed from it.
+    fn leave(&mut self, bracket: u8, start: I::Mark) -> Result<(), ParseError> {
+        self.depth -= 1;
+
+        // A type that takes a fixed number of elements stops before a trailing comma.
+        if self.options.allow_trailing_commas && self.peek()? == Some(b',') {
+            self.input.discard();
+        }
+
+        match self.peek()? {
+            Some(byte) if byte == bracket => {
+                self.input.discard();
+                Ok(())
+            }
+            None => Err(self.input.error_at(ErrorKind::UnbalancedBrackets, start)),
+            _ => Err(self.input.unexpected()),
+        }
+    }
+
+    /// Deserialize an integer type `T`, which also takes floats without a fractional part, such
+    /// as `-0` or `1e2`, like its `TryFrom<&Number>` conversion.
+    fn deserialize_integer<V, T>(
+        &mut self,
+        visitor: V,
+        visit: impl FnOnce(V, T) -> Result<V::Value, ParseError>,
+    ) -> Result<V::Value, ParseError>
+    where
+        V: Visitor<'de>,
+        T: for<'n> TryFrom<&'n Number, Error = ConversionError>,
+    {
+        if !matches!(self.peek()?, Some(b'-' | b'0'..=b'9')) {
+            return de::Deserializer::deserialize_any(self, visitor);
+        }
+
+        let start = self.input.mark();
+        let number = self.input.parse_number(self.options.allow_nan_infinity)?;
+
+        let value = match number {
+            Number::F64(_) => T::try_from(&number)
+                .map_err(de::Error::custom)
+                .and_then(|value| visit(visitor, value)),
+            _ => visit_number(&number, visitor),
+        };
+
+        value.map_err(|error| self.locate(error, start))
+    }
+}
+
+macro_rules! deserialize_integers {
+    ($($method:ident => $visit:ident($integer:ty)),*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParseError> {
+            self.deserialize_integer(visitor, |visitor, value: $integer| visitor.$visit(value))
+        }
+    )*};
+}
+
+impl<'de, I: Input<'de>> de::Deserializer<'de> for &mut Deserializer<'_, I> {
+    type Error = ParseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParseError> {
+        let next = self.peek()?;
+        let start = self.input.mark();
+
+        let value = match next {
+            Some(b'{') => {
+                self.enter()?;
+
+                visitor
+                    .visit_map(Members {
+                        deserializer: &mut *self,
+                        start,
+                        count: 0,
+                        keys: None,
+                    })
+                    .and_then(|value| self.leave(b'}', start).map(|()| value))
+            }
+            Some(b'[') => {
+                self.enter()?;
+
+                visitor
+                    .visit_seq(Elements {
+                        deserializer: &mut *self,
+                        start,
+                        count: 0,
+                    })
+                    .and_then(|value| self.leave(b']', start).map(|()| value))
+            }
+            Some(b'"') => visit_string(self.parse_string(b'"')?, visitor),
+            Some(b'\'') if self.options.allow_single_quotes => {
+                visit_string(self.parse_string(b'\'')?, visitor)
+            }
+            Some(b'-' | b'0'..=b'9') => visit_number(
+                &self.input.parse_number(self.options.allow_nan_infinity)?,
+                visitor,
+            ),
+            Some(b't') => {
+                self.input.parse_literal(b"true")?;
+                visitor.visit_bool(true)
+            }
+            Some(b'f') => {
+                self.input.parse_literal(b"false")?;
+                visitor.visit_bool(false)
+            }
+            Some(b'n') => {
+                self.input.parse_literal(b"null")?;
+                visitor.visit_unit()
+            }
+            Some(b'N') if self.options.allow_nan_infinity => {
+                self.input.parse_literal(b"NaN")?;
+                visitor.visit_f64(f64::NAN)
+            }
+            Some(b'I') if self.options.allow_nan_infinity => {
+                self.input.parse_literal(b"Infinity")?;
+                visitor.visit_f64(f64::INFINITY)
+            }
+            _ => Err(self.input.unexpected()),
+        };
+
+        value.map_err(|error| self.locate(error, start))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParseError> {
+        if self.peek()? == Some(b'n') {
+            self.input.parse_literal(b"null")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ParseError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Enums are read from the name of a unit variant as a string, or from an object with a
+    /// single member named after the variant, holding its content.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParseError> {
+        let next = self.peek()?;
+        let start = self.input.mark();
+
+        let value = match next {
+            Some(b'"') => visitor.visit_enum(self.parse_string(b'"')?.into_deserializer()),
+            Some(b'\'') if self.options.allow_single_quotes => {
+                visitor.visit_enum(self.parse_string(b'\'')?.into_deserializer())
+            }
+            Some(b'{') => {
+                self.enter()?;
+
+                visitor
+                    .visit_enum(Variant {
+                        deserializer: &mut *self,
+                        start,
+                    })
+                    .and_then(|value| self.leave(b'}', start).map(|()| value))
+            }
+            _ => de::Deserializer::deserialize_any(&mut *self, visitor),
+        };
+
+        value.map_err(|error| self.locate(error, start))
+    }
+
+    deserialize_integers! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128)
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+fn visit_string<'de, V: Visitor<'de>>(
+    string: Cow<'de, str>,
+    visitor: V,
+) -> Result<V::Value, ParseError> {
+    match string {
+        Cow::Borrowed(string) => visitor.visit_borrowed_str(string),
+        Cow::Owned(string) => visitor.visit_string(string),
+    }
+}
+
+fn visit_number<'de, V: Visitor<'de>>(number: &Number, visitor: V) -> Result<V::Value, ParseError> {
+    match number {
+        Number::I64(value) => visitor.visit_i64(*value),
+        Number::U64(value) => visitor.visit_u64(*value),
+        Number::F64(value) => visitor.visit_f64(*value),
+        Number::Raw(text) => {
+            if let Ok(value) = text.parse::<i128>() {
+                visitor.visit_i128(value)
+            } else if let Ok(value) = text.parse::<u128>() {
+                visitor.visit_u128(value)
+            } else if let Some(value) = number.as_f64() {
+                visitor.visit_f64(value)
+            } else {
+                Err(de::Error::custom("number out of range"))
+            }
+        }
+    }
+}
+
+/// The elements of an array, after its opening bracket.
+struct Elements<'d, 'o, 'de, I: Input<'de>> {
+    deserializer: &'d mut Deserializer<'o, I>,
+    start: I::Mark,
+    /// Number of elements read so far.
+    count: usize,
+}
+
+impl<'de, I: Input<'de>> SeqAccess<'de> for Elements<'_, '_, 'de, I> {
+    type Error = ParseError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ParseError> {
+        if !self
+            .deserializer
+            .next_element(self.count, b']', self.start)?
+        {
+            return Ok(None);
+        }
+
+        self.count += 1;
+
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+}
+
+/// The members of an object, after its opening bracket.
+struct Members<'d, 'o, 'de, I: Input<'de>> {
+    deserializer: &'d mut Deserializer<'o, I>,
+    start: I::Mark,
+    /// Number of members read so far.
+    count: usize,
+    /// Keys read so far, only kept for [`DuplicateKeys::Error`].
+    keys: Option<HashSet<String>>,
+}
+
+impl<'de, I: Input<'de>> MapAccess<'de> for Members<'_, '_, 'de, I> {
+    type Error = ParseError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ParseError> {
+        if !self
+            .deserializer
+            .next_element(self.count, b'}', self.start)?
+        {
+            return Ok(None);
+        }
+
+        self.count += 1;
+
+        let key_start = self.deserializer.input.mark();
+        let key = self.deserializer.parse_key(self.start)?;
+
+        if self.deserializer.options.duplicate_keys == DuplicateKeys::Error
+            && !self
+                .keys
+                .get_or_insert_with(HashSet::new)
+                .insert(key.to_string())
+        {
+            let error = ErrorKind::DuplicateKey(key.into_owned());
+            return Err(self.deserializer.input.error_at(error, key_start));
+        }
+
+        let key = match key {
+            Cow::Borrowed(key) => seed.deserialize(BorrowedStrDeserializer::new(key)),
+            Cow::Owned(key) => seed.deserialize(key.into_deserializer()),
+        };
+
+        key.map(Some)
+            .map_err(|error| self.deserializer.locate(error, key_start))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ParseError> {
+        self.deserializer.parse_colon(self.start)?;
+
+        seed.deserialize(&mut *self.deserializer)
+    }
+}
+
+/// An enum variant given as an object with a single member, after its opening bracket.
+struct Variant<'d, 'o, 'de, I: Input<'de>> {
+    deserializer: &'d mut Deserializer<'o, I>,
+    start: I::Mark,
+}
+
+impl<'de, I: Input<'de>> EnumAccess<'de> for Variant<'_, '_, 'de, I> {
+    type Error = ParseError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), ParseError> {
+        let key_start = self.deserializer.input.mark();
+        let variant = seed
+            .deserialize(self.deserializer.parse_key(self.start)?.into_deserializer())
+            .map_err(|error| self.deserializer.locate(error, key_start))?;
+
+        self.deserializer.parse_colon(self.start)?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de, I: Input<'de>> VariantAccess<'de> for Variant<'_, '_, 'de, I> {
+    type Error = ParseError;
+
+    fn unit_variant(self) -> Result<(), ParseError> {
+        <()>::deserialize(self.deserializer)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, ParseError> {
+        seed.deserialize(self.deserializer)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ParseError> {
+        de::Deserializer::deserialize_seq(self.deserializer, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParseError> {
+        de::Deserializer::deserialize_map(self.deserializer, visitor)
+    }
+}
+
+/// Deserializes any JSON value, from any self-describing format.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(Number::I64(value)))
+    }
+
+    fn visit_i128<E>(self, value: i128) -> Result<Value, E> {
+        Ok(Value::Number(i64::try_from(value).map_or_else(
+            |_| Number::Raw(value.to_string()),
+            Number::I64,
+        )))
+    }
+
+    /// Integers are [`Number::U64`] only if they don't fit [`Number::I64`], as when parsed.
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(
+            i64::try_from(value).map_or(Number::U64(value), Number::I64),
+        ))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Value, E> {
+        match u64::try_from(value) {
+            Ok(value) => self.visit_u64(value),
+            Err(_) => Ok(Value::Number(Number::Raw(value.to_string()))),
+        }
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::Number(Number::F64(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut elements: A) -> Result<Value, A::Error> {
+        let mut array = Vec::with_capacity(elements.size_hint().unwrap_or(0));
+
+        while let Some(element) = elements.next_element()? {
+            array.push(element);
+        }
+
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut members: A) -> Result<Value, A::Error> {
+        let mut object = Map::new();
+
+        while let Some((key, value)) = members.next_entry()? {
+            object.insert(key, value);
+        }
+
+        Ok(Value::Object(object))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        x0: f64,
+        y0: f64,
+        #[serde(default)]
+        label: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Segment(Point, Point),
+        Rectangle { width: u32, height: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Document<'a> {
+        name: &'a str,
+        escaped: Cow<'a, str>,
+        pairs: Vec<Point>,
+        shapes: Vec<Shape>,
+        counts: HashMap<String, (u8, i64)>,
+        initial: char,
+        nothing: (),
+        big: u128,
+    }
+
+    #[test]
+    fn types_are_deserialized() {
+        let input = r#"{
+            "name": "points",
+            "escaped": "tab\tand é",
+            "pairs": [{"x0": 1.5, "y0": -2}, {"x0": 0, "y0": 1e2, "label": "p"}],
+            "shapes": [
+                "Empty",
+                {"Circle": 2.5},
+                {"Segment": [{"x0": 0, "y0": 0}, {"x0": 1, "y0": 1, "label": null}]},
+                {"Rectangle": {"height": 2, "width": 3}}
+            ],
+            "counts": {"a": [1, -1], "b": [255, 9223372036854775807]},
+            "initial": "é",
+            "nothing": null,
+            "big": 340282366920938463463374607431768211455
+        }"#;
+
+        let document: Document = from_str(input).unwrap();
+
+        assert_eq!(document.name, "points");
+        assert!(matches!(document.escaped, Cow::Owned(ref escaped) if escaped == "tab\tand é"));
+        assert_eq!(
+            document.pairs,
+            [
+                Point {
+                    x0: 1.5,
+                    y0: -2.0,
+                    label: None
+                },
+                Point {
+                    x0: 0.0,
+                    y0: 100.0,
+                    label: Some("p".to_string())
+                }
+            ]
+        );
+        assert_eq!(
+            document.shapes[1..],
+            [
+                Shape::Circle(2.5),
+                Shape::Segment(
+                    Point {
+                        x0: 0.0,
+                        y0: 0.0,
+                        label: None
+                    },
+                    Point {
+                        x0: 1.0,
+                        y0: 1.0,
+                        label: None
+                    }
+                ),
+                Shape::Rectangle {
+                    width: 3,
+                    height: 2
+                }
+            ]
+        );
+        assert_eq!(document.shapes[0], Shape::Empty);
+        assert_eq!(document.counts["b"], (255, i64::MAX));
+        assert_eq!(document.initial, 'é');
+        assert_eq!(document.big, u128::MAX);
+
+        let from_reader: Vec<Shape> = from_reader(&br#"["Empty", {"Circle": 1}]"#[..]).unwrap();
+        assert_eq!(from_reader, [Shape::Empty, Shape::Circle(1.0)]);
+    }
+
+    #[test]
+    fn integers_take_floats_without_a_fractional_part() {
+        assert_eq!(
+            from_str::<(i32, u8, i64, u128)>("[1e2, 2.0, -0, 0.0]"),
+            Ok((100, 2, 0, 0))
+        );
+        assert_eq!(from_reader::<_, i8>(&b"-1.0"[..]), Ok(-1));
+    }
+
+    #[test]
+    fn values_match_the_parser() {
+        let inputs = [
+            r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377}],"a":{"b":[]}}"#,
+            r#"[true, false, null, "é𝄞\n", {}, [[]], -0.0, 1e300]"#,
+            "[0, -1, 9223372036854775807, 18446744073709551615, 123456789012345678901234567890]",
+            r#"{"a": 1, "b": 2, "a": 3}"#,
+        ];
+
+        for input in inputs {
+            let value = JsonParser::parse_from_bytes(input.as_bytes()).unwrap();
+
+            assert_eq!(from_slice::<Value>(input.as_bytes()), Ok(value.clone()));
+            assert_eq!(from_reader::<_, Value>(input.as_bytes()), Ok(value));
+        }
+    }
+
+    #[test]
+    fn errors_are_positioned() {
+        let error = |input: &str| {
+            let error = from_str::<Vec<Point>>(input).unwrap_err();
+            (error.kind().clone(), error.line(), error.column())
+        };
+        let custom = |message: &str| ErrorKind::Custom(message.to_string());
+
+        assert_eq!(
+            error("[{\"x0\": 1, \"y0\": \"2\"}]"),
+            (custom("invalid type: string \"2\", expected f64"), 1, 18)
+        );
+        assert_eq!(
+            error("[\n  {\"x0\": 1, \"y0\": 2},\n  {\"x0\": 1}\n]"),
+            (custom("missing field `y0`"), 3, 3)
+        );
+        assert_eq!(
+            error("[{\"x0\": 1, \"x0\": 2}]"),
+            (custom("duplicate field `x0`"), 1, 2)
+        );
+        assert_eq!(
+            error("{}"),
+            (custom("invalid type: map, expected a sequence"), 1, 1)
+        );
+        assert_eq!(error("[] []"), (ErrorKind::TrailingCharacters, 1, 4));
+        assert_eq!(
+            error("[{\"x0\": 1 \"y0\": 2}]"),
+            (ErrorKind::UnexpectedCharacter('"'), 1, 11)
+        );
+        assert_eq!(
+            error("[{\"x0\": 1]"),
+            (ErrorKind::UnbalancedBrackets, 1, 10)
+        );
+        assert_eq!(error("[{\"x0\": 1,"), (ErrorKind::UnbalancedBrackets, 1, 2));
+
+        let error = from_str::<Shape>(r#"{"Square": 1}"#).unwrap_err();
+        assert_eq!(error.column(), 2);
+        assert!(error.to_string().starts_with("unknown variant `Square`"));
+
+        let error = from_str::<(u8, u8)>("[1, 2, 3]").unwrap_err();
+        assert_eq!(
+            (error.kind(), error.column()),
+            (&ErrorKind::UnexpectedCharacter(','), 6)
+        );
+
+        let error = from_str::<Vec<u8>>("[1, 2.5]").unwrap_err();
+        assert_eq!(
+            (error.kind().to_string(), error.column()),
+            (
+                "expected u8, found a number with a fractional part".to_string(),
+                5
+            )
+        );
+    }
+
+    #[test]
+    fn options_apply() {
+        let options = ParserOptions::new()
+            .allow_comments(true)
+            .allow_trailing_commas(true)
+            .allow_single_quotes(true);
+        let point: Point = options
+            .deserialize_from_bytes(b"{'x0': 1, /* y */ \"y0\": 2,} // point")
+            .unwrap();
+        assert_eq!((point.x0, point.y0), (1.0, 2.0));
+
+        let pair: (u8, u8) = options.deserialize_from_bytes(b"[1, 2, ]").unwrap();
+        assert_eq!(pair, (1, 2));
+
+        let error = |options: ParserOptions, input: &[u8]| {
+            options
+                .deserialize_from_bytes::<Value>(input)
+                .unwrap_err()
+                .kind()
+                .clone()
+        };
+
+        assert_eq!(
+            error(ParserOptions::new().max_depth(2), b"[[[]]]"),
+            ErrorKind::NestingTooDeep
+        );
+        assert_eq!(
+            error(ParserOptions::new().max_elements(2), b"{\"a\": [1, 2, 3]}"),
+            ErrorKind::TooManyElements
+        );
+        assert_eq!(
+            error(ParserOptions::new().max_string_length(3), b"[\"four\"]"),
+            ErrorKind::StringTooLong
+        );
+        assert_eq!(
+            error(
+                ParserOptions::new().duplicate_keys(DuplicateKeys::Error),
+                b"[{\"a\": 1, \"a\": 2}]"
+            ),
+            ErrorKind::DuplicateKey("a".to_string())
+        );
+        assert_eq!(
+            error(ParserOptions::new().max_document_size(4), b"[1, 2]"),
+            ErrorKind::DocumentTooLarge
+        );
+
+        let error = ParserOptions::new()
+            .max_document_size(4)
+            .deserialize::<_, Value>(&b"[1, 2]"[..])
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DocumentTooLarge);
+    }
+}
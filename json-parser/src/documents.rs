@@ -0,0 +1,162 @@
+use crate::error::{ParseError, Position};
+use crate::input::Input;
+use crate::options::ParserOptions;
+use crate::parser::JsonParser;
+use crate::reader::JsonReader;
+use crate::value::Value;
+use std::io::Read;
+
+/// An iterator over a stream of concatenated JSON documents, such as `{"a": 1}{"b": 2}` or one
+/// value per line, read from any [`Read`].
+///
+/// Documents can be separated by whitespace, but don't have to be unless they would otherwise run
+/// together, as in `1 2`. Each one is parsed as it's reached, so streams can be processed one
+/// document at a time no matter how long they are. After an error, which is returned for the
+/// document it occurred in, nothing more is read.
+///
+/// A maximum document size in the options applies to the stream as a whole.
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::parser::JsonParser;
+/// use json_parser::value::Value;
+///
+/// let input: &[u8] = b"{\"a\": 1}\n[2] \"three\"";
+/// let documents = JsonParser::parse_documents(input)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(documents.len(), 3);
+/// assert_eq!(documents[2], Value::String("three".to_string()));
+/// ```
+pub struct Documents<R> {
+    reader: JsonReader<R>,
+    options: ParserOptions,
+    /// Whether the end of input or an error was reached.
+    done: bool,
+}
+
+impl<R> Documents<R>
+where
+    R: Read,
+{
+    pub(crate) fn new(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader: JsonReader::new(reader).with_max_size(options.max_document_size),
+            options,
+            done: false,
+        }
+    }
+
+    /// Position of the next byte to be read, which is right after the last document returned.
+    #[must_use]
+    pub fn position(&self) -> Position {
+        self.reader.position()
+    }
+
+    fn read_document(&mut self) -> Result<Option<Value>, ParseError> {
+        JsonParser::skip_whitespace(&mut self.reader, &self.options)?;
+
+        if self.reader.peek()?.is_none() {
+            return Ok(None);
+        }
+
+        JsonParser::parse_value(&mut self.reader, &self.options, 0).map(Some)
+    }
+}
+
+impl<R> Iterator for Documents<R>
+where
+    R: Read,
+{
+    type Item = Result<Value, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let document = self.read_document().transpose();
+        self.done = !matches!(document, Some(Ok(_)));
+
+        document
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn parse_documents(input: &str) -> Vec<Result<String, ErrorKind>> {
+        JsonParser::parse_documents(input.as_bytes())
+            .map(|document| {
+                document
+                    .map(|value| value.to_string())
+                    .map_err(|error| error.kind().clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn concatenated_documents() {
+        assert_eq!(
+            parse_documents(r#"{"a":1}{"b":2}[3]"four"null"#),
+            [
+                Ok(r#"{"a":1}"#.to_string()),
+                Ok(r#"{"b":2}"#.to_string()),
+                Ok("[3]".to_string()),
+                Ok(r#""four""#.to_string()),
+                Ok("null".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_documents(" 1 2\n\n3 \n"),
+            [
+                Ok("1".to_string()),
+                Ok("2".to_string()),
+                Ok("3".to_string())
+            ]
+        );
+        assert_eq!(parse_documents(""), []);
+        assert_eq!(parse_documents(" \n "), []);
+    }
+
+    #[test]
+    fn errors_end_the_stream() {
+        assert_eq!(
+            parse_documents("[1] ] [2]"),
+            [
+                Ok("[1]".to_string()),
+                Err(ErrorKind::UnexpectedCharacter(']'))
+            ]
+        );
+        assert_eq!(
+            parse_documents("1 [2, 3"),
+            [Ok("1".to_string()), Err(ErrorKind::UnbalancedBrackets)]
+        );
+
+        let mut documents = JsonParser::parse_documents(&b"{\"a\": 1}\n{\"a\" 2}\n{}"[..]);
+        assert!(documents.next().unwrap().is_ok());
+        assert_eq!(documents.position().line, 1);
+
+        let error = documents.next().unwrap().unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, 6));
+        assert!(documents.next().is_none());
+    }
+
+    #[test]
+    fn options_apply_to_every_document() {
+        let options = ParserOptions::new().allow_comments(true).max_depth(1);
+        let input: &[u8] = b"// first\n[1] /* second */ [2]\n[[3]]";
+        let documents: Vec<_> = options.parse_documents(input).collect();
+
+        assert_eq!(documents.len(), 3);
+        assert!(documents[..2].iter().all(Result::is_ok));
+        assert_eq!(
+            documents[2].as_ref().unwrap_err().kind(),
+            &ErrorKind::NestingTooDeep
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// The category of failure encountered while parsing JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A character that cannot start or continue any JSON token.
+    UnexpectedCharacter(char),
+    /// Input ended where a value, key or punctuation was expected.
+    UnexpectedEndOfInput,
+    /// Input ended before the closing quote of a string.
+    UnterminatedString,
+    /// A string containing bytes that aren't valid UTF-8.
+    InvalidUtf8,
+    /// A backslash in a string followed by a character that isn't a valid escape.
+    InvalidEscape,
+    /// A `\uXXXX` escape with malformed hex digits, or a UTF-16 surrogate without its pair.
+    InvalidUnicodeEscape,
+    /// A control character (U+0000 to U+001F) inside a string that wasn't escaped.
+    ControlCharacterInString(char),
+    /// A bare word that isn't exactly `true`, `false` or `null`.
+    InvalidLiteral,
+    /// A number that does not follow the JSON number grammar or doesn't fit its representation.
+    InvalidNumber,
+    /// Non-whitespace content after the top-level value was complete.
+    TrailingCharacters,
+    /// A closing bracket without a matching opening bracket, or input ending with unclosed
+    /// brackets.
+    UnbalancedBrackets,
+    /// A key that already appeared in the same object, rejected by
+    /// [`crate::options::DuplicateKeys::Error`].
+    DuplicateKey(String),
+    /// Input ended inside a `/* */` comment.
+    UnterminatedComment,
+    /// Arrays and objects nested deeper than [`crate::options::ParserOptions::max_depth`].
+    NestingTooDeep,
+    /// A string longer than [`crate::options::ParserOptions::max_string_length`].
+    StringTooLong,
+    /// An array or object with more than [`crate::options::ParserOptions::max_elements`].
+    TooManyElements,
+    /// Input longer than [`crate::options::ParserOptions::max_document_size`].
+    DocumentTooLarge,
+    /// Reading the input failed.
+    Io(io::ErrorKind),
+    /// The input didn't fit the type it was deserialized into, with the `serde` feature.
+    Custom(String),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::UnexpectedCharacter(character) => {
+                write!(f, "unexpected character {character:?}")
+            }
+            ErrorKind::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            ErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8 in string"),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            ErrorKind::InvalidUnicodeEscape => write!(f, "invalid unicode escape sequence"),
+            ErrorKind::ControlCharacterInString(character) => {
+                write!(f, "unescaped control character {character:?} in string")
+            }
+            ErrorKind::InvalidLiteral => write!(f, "invalid literal"),
+            ErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ErrorKind::TrailingCharacters => write!(f, "trailing characters after JSON value"),
+            ErrorKind::UnbalancedBrackets => write!(f, "unbalanced brackets"),
+            ErrorKind::DuplicateKey(key) => write!(f, "duplicate key {key:?}"),
+            ErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            ErrorKind::NestingTooDeep => write!(f, "arrays and objects nested too deeply"),
+            ErrorKind::StringTooLong => write!(f, "string too long"),
+            ErrorKind::TooManyElements => write!(f, "too many elements in array or object"),
+            ErrorKind::DocumentTooLarge => write!(f, "document too large"),
+            ErrorKind::Io(kind) => write!(f, "I/O error ({kind})"),
+            ErrorKind::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+/// A location in the input data.
+///
+/// `offset` is the 0-based byte offset, while `line` and `column` are 1-based, with columns
+/// counted in characters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+/// Error returned when input data is not valid JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl ParseError {
+    #[must_use]
+    pub fn new(kind: ErrorKind, position: Position) -> Self {
+        Self { kind, position }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    #[must_use]
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// 0-based byte offset at which the error was detected.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.position.offset
+    }
+
+    /// 1-based line at which the error was detected.
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.position.line
+    }
+
+    /// 1-based column at which the error was detected.
+    #[must_use]
+    pub fn column(&self) -> usize {
+        self.position.column
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {} (byte {})",
+            self.kind, self.position.line, self.position.column, self.position.offset
+        )
+    }
+}
+
+impl Error for ParseError {}
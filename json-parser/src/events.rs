@@ -0,0 +1,636 @@
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::input::Input;
+use crate::options::ParserOptions;
+use crate::parser::JsonParser;
+use crate::pointer;
+use crate::reader::JsonReader;
+use crate::value::{Number, Value};
+use std::io::Read;
+
+/// A piece of a JSON document, in the order it appears in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    /// The key of the object member whose value comes next.
+    Key(String),
+    EndObject,
+    StartArray,
+    EndArray,
+    String(String),
+    Number(Number),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// What the reader expects to come next.
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+    /// A value, at the top level, after a colon or after a comma in an array.
+    Value,
+    /// A value or `]`, right after `[`.
+    FirstElement,
+    /// A key or `}`, right after `{`.
+    FirstKey,
+    /// A key, after a comma in an object.
+    Key,
+    /// A comma or closing bracket, or the end of input at the top level.
+    AfterValue,
+    /// The document is complete.
+    End,
+    /// The document is invalid, and reading stopped at this error.
+    Failed(ParseError),
+}
+
+/// A pull parser that reads JSON from any [`Read`] as a sequence of [`Event`]s.
+///
+/// Only the currently open objects and arrays are kept track of, so documents can be processed
+/// in constant memory no matter how large they are, e.g. one element of a huge array at a time
+/// with [`EventReader::elements`]. Errors are the same as those of [`JsonParser::parse`], and are
+/// returned again by any later call once one occurred.
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::events::{Event, EventReader};
+///
+/// let input: &[u8] = br#"{"a": [1, null]}"#;
+/// let events = EventReader::new(input).collect::<Result<Vec<_>, _>>().unwrap();
+///
+/// assert_eq!(events[0], Event::StartObject);
+/// assert_eq!(events[1], Event::Key("a".to_string()));
+/// assert_eq!(events.len(), 7);
+/// ```
+pub struct EventReader<R> {
+    reader: JsonReader<R>,
+    /// Currently open containers, along with where they were opened.
+    open: Vec<(Container, Position)>,
+    state: State,
+    /// Used for the values built by [`EventReader::elements`].
+    options: ParserOptions,
+}
+
+impl<R> EventReader<R>
+where
+    R: Read,
+{
+    /// Create a new [`EventReader`] that reads a single document from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// Create a new [`EventReader`] that builds the values returned by
+    /// [`EventReader::elements`] according to `options`.
+    ///
+    /// Events themselves are always read as standard JSON, only stopping at the maximum document
+    /// size and nesting depth.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader: JsonReader::new(reader).with_max_size(options.max_document_size),
+            open: Vec::new(),
+            state: State::Value,
+            options,
+        }
+    }
+
+    /// Position of the next byte to be read.
+    #[must_use]
+    pub fn position(&self) -> Position {
+        self.reader.position()
+    }
+
+    /// Number of objects and arrays that are currently open.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Read the next event, or `None` once the document is complete.
+    pub fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        if let State::Failed(error) = &self.state {
+            return Err(error.clone());
+        }
+
+        self.read_event().inspect_err(|error| {
+            self.state = State::Failed(error.clone());
+        })
+    }
+
+    /// Read through the rest of the document, checking that it's valid.
+    pub fn finish(&mut self) -> Result<(), ParseError> {
+        while self.next_event()?.is_some() {}
+
+        Ok(())
+    }
+
+    /// Iterate over the elements of the array at `pointer`, a JSON Pointer (RFC 6901) such as
+    /// `/pairs` or `/data/0/items`, relative to the next value in the input.
+    ///
+    /// Only one element is held in memory at a time, and everything before the array is skipped
+    /// without building any values. Once the iterator is exhausted, the reader is positioned
+    /// right after the array, so [`EventReader::finish`] checks the rest of the document.
+    ///
+    /// Returns `None` if `pointer` is malformed or doesn't lead to an array, in which case the
+    /// reader stops wherever that became apparent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::events::EventReader;
+    /// use json_parser::value::Value;
+    ///
+    /// let input: &[u8] = br#"{"count": 2, "pairs": [{"x0": 1.5}, {"x0": 2.5}]}"#;
+    /// let mut events = EventReader::new(input);
+    ///
+    /// let pairs = events.elements("/pairs").unwrap().unwrap();
+    /// let pairs = pairs.collect::<Result<Vec<Value>, _>>().unwrap();
+    ///
+    /// assert_eq!(pairs.len(), 2);
+    /// events.finish().unwrap();
+    /// ```
+    pub fn elements(&mut self, pointer: &str) -> Result<Option<Elements<'_, R>>, ParseError> {
+        let Some(tokens) = pointer::tokens(pointer) else {
+            return Ok(None);
+        };
+
+        let mut event = self.next_event()?;
+
+        for token in tokens {
+            event = match event {
+                Some(Event::StartObject) => self.find_member(&token)?,
+                Some(Event::StartArray) => match pointer::array_index(&token) {
+                    Some(index) => self.find_element(index)?,
+                    None => return Ok(None),
+                },
+                _ => return Ok(None),
+            };
+        }
+
+        if event != Some(Event::StartArray) {
+            return Ok(None);
+        }
+
+        Ok(Some(Elements {
+            events: self,
+            done: false,
+        }))
+    }
+
+    fn read_event(&mut self) -> Result<Option<Event>, ParseError> {
+        self.reader.skip_whitespace()?;
+
+        let byte = self.reader.peek()?;
+
+        match self.state {
+            State::FirstElement if byte == Some(b']') => Ok(Some(self.close(Container::Array))),
+            State::Value | State::FirstElement => self.read_value_event(byte).map(Some),
+            State::FirstKey if byte == Some(b'}') => Ok(Some(self.close(Container::Object))),
+            State::FirstKey | State::Key => self.read_key(byte).map(Some),
+            State::AfterValue => self.read_after_value(byte),
+            State::End | State::Failed(_) => Ok(None),
+        }
+    }
+
+    fn read_value_event(&mut self, byte: Option<u8>) -> Result<Event, ParseError> {
+        let event = match byte {
+            Some(b'{' | b'[') if self.open.len() >= self.options.max_depth => {
+                return Err(self.reader.error(ErrorKind::NestingTooDeep));
+            }
+            Some(b'{') => return Ok(self.open(Container::Object)),
+            Some(b'[') => return Ok(self.open(Container::Array)),
+            Some(b'"') => Event::String(self.reader.parse_string()?.into_owned()),
+            Some(b'-' | b'0'..=b'9') => Event::Number(self.reader.parse_number(false)?),
+            Some(b't') => self
+                .reader
+                .parse_literal(b"true")
+                .map(|()| Event::Boolean(true))?,
+            Some(b'f') => self
+                .reader
+                .parse_literal(b"false")
+                .map(|()| Event::Boolean(false))?,
+            Some(b'n') => self.reader.parse_literal(b"null").map(|()| Event::Null)?,
+            _ => return Err(self.reader.unexpected()),
+        };
+
+        self.end_value();
+
+        Ok(event)
+    }
+
+    /// Read a key along with the colon after it.
+    fn read_key(&mut self, byte: Option<u8>) -> Result<Event, ParseError> {
+        let key = match byte {
+            Some(b'"') => self.reader.parse_string()?.into_owned(),
+            None => return Err(self.unclosed()),
+            _ => return Err(self.reader.unexpected()),
+        };
+
+        self.reader.skip_whitespace()?;
+
+        match self.reader.peek()? {
+            Some(b':') => self.reader.discard(),
+            None => return Err(self.unclosed()),
+            _ => return Err(self.reader.unexpected()),
+        }
+
+        self.state = State::Value;
+
+        Ok(Event::Key(key))
+    }
+
+    fn read_after_value(&mut self, byte: Option<u8>) -> Result<Option<Event>, ParseError> {
+        let Some((container, _)) = self.open.last() else {
+            return match byte {
+                None => {
+                    self.state = State::End;
+                    Ok(None)
+                }
+                Some(_) => Err(self.reader.error(ErrorKind::TrailingCharacters)),
+            };
+        };
+
+        match (container, byte) {
+            (_, Some(b',')) => {
+                self.reader.discard();
+                self.state = match container {
+                    Container::Object => State::Key,
+                    Container::Array => State::Value,
+                };
+
+                self.read_event()
+            }
+            (Container::Object, Some(b'}')) => Ok(Some(self.close(Container::Object))),
+            (Container::Array, Some(b']')) => Ok(Some(self.close(Container::Array))),
+            (_, Some(b'}' | b']')) => Err(self.reader.error(ErrorKind::UnbalancedBrackets)),
+            (_, None) => Err(self.unclosed()),
+            (_, Some(_)) => Err(self.reader.unexpected()),
+        }
+    }
+
+    /// Consume the opening bracket of `container`.
+    fn open(&mut self, container: Container) -> Event {
+        self.open.push((container, self.reader.position()));
+        self.reader.discard();
+
+        match container {
+            Container::Object => {
+                self.state = State::FirstKey;
+                Event::StartObject
+            }
+            Container::Array => {
+                self.state = State::FirstElement;
+                Event::StartArray
+            }
+        }
+    }
+
+    /// Consume the closing bracket of the innermost container, which must be `container`.
+    fn close(&mut self, container: Container) -> Event {
+        self.open.pop();
+        self.reader.discard();
+        self.end_value();
+
+        match container {
+            Container::Object => Event::EndObject,
+            Container::Array => Event::EndArray,
+        }
+    }
+
+    fn end_value(&mut self) {
+        self.state = State::AfterValue;
+    }
+
+    /// Error for input ending inside the innermost container, reported where it was opened.
+    fn unclosed(&self) -> ParseError {
+        let start = self
+            .open
+            .last()
+            .map_or_else(|| self.reader.position(), |(_, start)| *start);
+
+        self.reader.error_at(ErrorKind::UnbalancedBrackets, start)
+    }
+
+    /// Skip the rest of the value that `event` starts.
+    fn skip(&mut self, event: Option<&Event>) -> Result<(), ParseError> {
+        if let Some(Event::StartObject | Event::StartArray) = event {
+            let depth = self.open.len();
+
+            while self.open.len() >= depth && self.next_event()?.is_some() {}
+        }
+
+        Ok(())
+    }
+
+    /// Find the member called `key` of the object that was just opened, returning the first
+    /// event of its value, or `None` if there's no such member.
+    fn find_member(&mut self, key: &str) -> Result<Option<Event>, ParseError> {
+        loop {
+            match self.next_event()? {
+                Some(Event::Key(name)) if name == key => return self.next_event(),
+                Some(Event::Key(_)) => {
+                    let event = self.next_event()?;
+                    self.skip(event.as_ref())?;
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    /// Find element `index` of the array that was just opened, returning its first event, or
+    /// `None` if the array is shorter than that.
+    fn find_element(&mut self, index: usize) -> Result<Option<Event>, ParseError> {
+        for _ in 0..index {
+            match self.next_event()? {
+                Some(Event::EndArray) | None => return Ok(None),
+                event => self.skip(event.as_ref())?,
+            }
+        }
+
+        match self.next_event()? {
+            Some(Event::EndArray) => Ok(None),
+            event => Ok(event),
+        }
+    }
+
+    /// Read the next element of the innermost array as a whole, or `None` at its end.
+    fn next_element(&mut self) -> Result<Option<Value>, ParseError> {
+        self.reader.skip_whitespace()?;
+
+        if self.state == State::AfterValue && self.reader.peek()? == Some(b',') {
+            self.reader.discard();
+            self.state = State::Value;
+        }
+
+        let has_element = match self.state {
+            State::Value => true,
+            State::FirstElement => {
+                self.reader.skip_whitespace()?;
+                self.reader.peek()? != Some(b']')
+            }
+            _ => false,
+        };
+
+        if has_element {
+            let value = JsonParser::parse_value(&mut self.reader, &self.options, self.open.len())?;
+            self.end_value();
+
+            return Ok(Some(value));
+        }
+
+        // Anything else is either the closing bracket or an error.
+        self.read_event().map(|_| None)
+    }
+}
+
+impl<R> Iterator for EventReader<R>
+where
+    R: Read,
+{
+    type Item = Result<Event, ParseError>;
+
+    /// Returns the next event, or `None` once the document is complete or after an error.
+    fn next(&mut self) -> Option<Self::Item> {
+        if let State::Failed(_) = self.state {
+            return None;
+        }
+
+        self.next_event().transpose()
+    }
+}
+
+/// Iterator over the elements of an array, returned by [`EventReader::elements`].
+pub struct Elements<'r, R> {
+    events: &'r mut EventReader<R>,
+    done: bool,
+}
+
+impl<R> Iterator for Elements<'_, R>
+where
+    R: Read,
+{
+    type Item = Result<Value, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let element = if let State::Failed(error) = &self.events.state {
+            Err(error.clone())
+        } else {
+            self.events.next_element().inspect_err(|error| {
+                self.events.state = State::Failed(error.clone());
+            })
+        };
+
+        self.done = !matches!(element, Ok(Some(_)));
+
+        element.transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::map::Map;
+
+    /// Build a [`Value`] back out of events, to compare against the parser.
+    fn build(events: &mut EventReader<&[u8]>) -> Result<Value, ParseError> {
+        let value = build_value(events, None)?;
+        events.finish()?;
+
+        Ok(value)
+    }
+
+    fn build_value(
+        events: &mut EventReader<&[u8]>,
+        first: Option<Event>,
+    ) -> Result<Value, ParseError> {
+        let event = match first {
+            Some(event) => event,
+            None => events.next_event()?.unwrap(),
+        };
+
+        Ok(match event {
+            Event::StartObject => {
+                let mut object = Map::new();
+
+                loop {
+                    match events.next_event()?.unwrap() {
+                        Event::Key(key) => {
+                            object.insert(key, build_value(events, None)?);
+                        }
+                        Event::EndObject => break Value::Object(object),
+                        event => panic!("Unexpected {event:?} in object"),
+                    }
+                }
+            }
+            Event::StartArray => {
+                let mut array = Vec::new();
+
+                loop {
+                    match events.next_event()?.unwrap() {
+                        Event::EndArray => break Value::Array(array),
+                        event => array.push(build_value(events, Some(event))?),
+                    }
+                }
+            }
+            Event::String(string) => Value::String(string),
+            Event::Number(number) => Value::Number(number),
+            Event::Boolean(boolean) => Value::Boolean(boolean),
+            Event::Null => Value::Null,
+            event => panic!("Unexpected {event:?} at start of value"),
+        })
+    }
+
+    const INPUTS: &[&str] = &[
+        r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377},{"sample":"string \"sample\"","nullable":null,"flags":[true,false]}]}"#,
+        " { \"a\" : [ { } , [ ] , { \"b\" : [ null ] } ] } ",
+        r#""top-level string""#,
+        "-12.5e3",
+        "[]",
+        "{}",
+        "",
+        " \n ",
+        "[1 2]",
+        r#"{"a": 1 "b": 2}"#,
+        "[1: 2]",
+        r#"{"a" 1}"#,
+        r#"{"a":: 1}"#,
+        "[1,]",
+        "[,1]",
+        r#"{"a": 1,}"#,
+        "{1: 2}",
+        r#"{"a": [1, 2}"#,
+        r#"{"a": {"b": [1]]"#,
+        "[[[]]",
+        "[]]",
+        r#"{"a": 1} {"b": 2}"#,
+        r#"{"a""#,
+        r#"{"a":"#,
+        "[1,",
+        "[truX]",
+        "{\n  \"key\": ;\n}",
+    ];
+
+    #[test]
+    fn events_match_parser() {
+        for input in INPUTS {
+            let expected = JsonParser::parse_from_bytes(input.as_bytes());
+            let value = build(&mut EventReader::new(input.as_bytes()));
+
+            assert_eq!(value, expected, "Reading events from {input:?}");
+        }
+    }
+
+    #[test]
+    fn event_sequence() {
+        let input: &[u8] = br#"{"a": [1, "b", true], "c": {}}"#;
+        let events = EventReader::new(input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::StartObject,
+                Event::Key("a".to_string()),
+                Event::StartArray,
+                Event::Number(Number::I64(1)),
+                Event::String("b".to_string()),
+                Event::Boolean(true),
+                Event::EndArray,
+                Event::Key("c".to_string()),
+                Event::StartObject,
+                Event::EndObject,
+                Event::EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_sticky() {
+        let input: &[u8] = b"[1, ]";
+        let mut events = EventReader::new(input);
+
+        assert_eq!(events.next_event(), Ok(Some(Event::StartArray)));
+        assert_eq!(events.next_event(), Ok(Some(Event::Number(Number::I64(1)))));
+
+        let error = events.next_event().unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(']'));
+        assert_eq!(events.next_event(), Err(error));
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn elements_at_pointer() {
+        let input: &[u8] =
+            br#"{"skip": {"pairs": [0]}, "pairs": [{"x0": 1}, [2, 3], "a"], "b": 4}"#;
+        let mut events = EventReader::new(input);
+
+        let elements = events
+            .elements("/pairs")
+            .unwrap()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[2], Value::String("a".to_string()));
+        assert_eq!(events.next_event(), Ok(Some(Event::Key("b".to_string()))));
+        events.finish().unwrap();
+    }
+
+    #[test]
+    fn nested_pointers() {
+        let input: &[u8] = br#"{"a/b": [[], {"~c": [[1], [2, 3]]}], "d": []}"#;
+
+        let elements = |pointer: &str| {
+            EventReader::new(input)
+                .elements(pointer)
+                .unwrap()
+                .map(|elements| elements.collect::<Result<Vec<_>, _>>().unwrap())
+        };
+
+        assert_eq!(elements("").unwrap_or_default(), vec![]);
+        assert_eq!(elements("/a~1b").unwrap().len(), 2);
+        assert_eq!(elements("/a~1b/0"), Some(vec![]));
+        assert_eq!(elements("/a~1b/1/~0c/1").unwrap().len(), 2);
+        assert_eq!(elements("/d"), Some(vec![]));
+
+        assert_eq!(elements("/a~1b/1/~0c/2"), None);
+        assert_eq!(elements("/a~1b/01"), None);
+        assert_eq!(elements("/a~1b/1"), None);
+        assert_eq!(elements("/missing"), None);
+        assert_eq!(elements("a~1b"), None);
+        assert_eq!(elements("/a~2b"), None);
+    }
+
+    #[test]
+    fn element_errors() {
+        let input: &[u8] = br#"{"pairs": [{"x0": 1}, {"x0": ]}"#;
+        let mut events = EventReader::new(input);
+        let mut elements = events.elements("/pairs").unwrap().unwrap();
+
+        assert!(matches!(elements.next(), Some(Ok(Value::Object(_)))));
+
+        let error = elements.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(']'));
+        assert_eq!(error.offset(), 29);
+        assert!(elements.next().is_none());
+
+        assert_eq!(events.finish(), Err(error));
+
+        let input: &[u8] = br#"{"pairs": [1 2]}"#;
+        let mut events = EventReader::new(input);
+        let elements = events.elements("/pairs").unwrap().unwrap();
+        let error = elements.collect::<Result<Vec<_>, _>>().unwrap_err();
+
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('2'));
+    }
+}
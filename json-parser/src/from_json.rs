@@ -0,0 +1,458 @@
+use crate::map::Map;
+use crate::value::{ConversionError, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::hash::BuildHasher;
+
+#[cfg(feature = "derive")]
+pub use json_parser_derive::FromJson;
+
+/// A type that can be built from a [`Value`].
+///
+/// This is implemented for primitives, strings, `Option`, `Vec`, `HashMap` with string keys,
+/// tuples and arrays, and can be derived for structs with named fields with the `derive` feature.
+/// Fields are read from the members with the same name, ignoring any others, and can be
+/// customized with `#[json(...)]` attributes:
+///
+/// * `rename = "name"` reads the field from the member called `name`.
+/// * `default` uses [`Default::default`] if the member is missing, and `default = "path"` calls
+///   the function at `path` instead.
+///
+/// Fields of type `Option` are `None` if their member is missing or `null`.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use json_parser::from_json::FromJson;
+/// use json_parser::parser::JsonParser;
+///
+/// #[derive(FromJson)]
+/// struct Pair {
+///     x0: f64,
+///     #[json(rename = "y-0")]
+///     y0: f64,
+///     #[json(default)]
+///     label: String,
+/// }
+///
+/// let value = JsonParser::parse_from_bytes(br#"[{"x0": 1.5, "y-0": 2}]"#).unwrap();
+/// let pairs = Vec::<Pair>::from_json(&value).unwrap();
+///
+/// assert_eq!((pairs[0].x0, pairs[0].y0, pairs[0].label.as_str()), (1.5, 2.0, ""));
+///
+/// let value = JsonParser::parse_from_bytes(br#"[{"x0": 1.5, "y-0": true}]"#).unwrap();
+/// let error = Vec::<Pair>::from_json(&value).err().unwrap();
+///
+/// assert_eq!(error.to_string(), "expected a number, found a boolean at /0/y-0");
+/// # }
+/// ```
+pub trait FromJson: Sized {
+    /// Convert `value`, failing if it doesn't have the expected shape.
+    fn from_json(value: &Value) -> Result<Self, FromJsonError>;
+
+    /// The value to use for a missing object member, or `None` if it's required.
+    #[must_use]
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Error returned when a [`Value`] can't be converted with [`FromJson`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromJsonError {
+    message: String,
+    /// Keys and indexes leading to the value that failed, innermost first.
+    path: Vec<String>,
+}
+
+impl FromJsonError {
+    /// Error for a value of the wrong type, where `expected` describes what was expected, such as
+    /// `"an object"`.
+    #[must_use]
+    pub fn expected(expected: &str, found: &Value) -> Self {
+        Self::custom(format!("expected {expected}, found {}", found.type_name()))
+    }
+
+    /// Error for an object without a required member.
+    #[must_use]
+    pub fn missing_field(name: &str) -> Self {
+        Self::custom(format!("missing field {name:?}"))
+    }
+
+    /// Error with any other message.
+    #[must_use]
+    pub fn custom(message: impl Display) -> Self {
+        Self {
+            message: message.to_string(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Record that the error occurred in the object member `name`.
+    #[must_use]
+    pub fn in_field(mut self, name: &str) -> Self {
+        self.path.push(name.replace('~', "~0").replace('/', "~1"));
+        self
+    }
+
+    /// Record that the error occurred in the array element at `index`.
+    #[must_use]
+    pub fn in_element(mut self, index: usize) -> Self {
+        self.path.push(index.to_string());
+        self
+    }
+
+    /// What went wrong, without the location.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// JSON Pointer to the value that failed to convert, which is empty for the top-level value.
+    #[must_use]
+    pub fn path(&self) -> String {
+        self.path
+            .iter()
+            .rev()
+            .fold(String::new(), |path, token| path + "/" + token)
+    }
+}
+
+impl Display for FromJsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at {}", self.message, self.path())
+        }
+    }
+}
+
+impl Error for FromJsonError {}
+
+impl From<ConversionError> for FromJsonError {
+    fn from(error: ConversionError) -> Self {
+        Self::custom(error)
+    }
+}
+
+/// Convert the member `name` of `object`, or use [`FromJson::from_missing`] if there isn't one.
+pub fn field<T: FromJson>(object: &Map<String, Value>, name: &str) -> Result<T, FromJsonError> {
+    match object.get(name) {
+        Some(value) => T::from_json(value).map_err(|error| error.in_field(name)),
+        None => T::from_missing().ok_or_else(|| FromJsonError::missing_field(name)),
+    }
+}
+
+/// Convert the member `name` of `object`, or call `default` if there isn't one.
+pub fn field_or_else<T: FromJson>(
+    object: &Map<String, Value>,
+    name: &str,
+    default: impl FnOnce() -> T,
+) -> Result<T, FromJsonError> {
+    match object.get(name) {
+        Some(value) => T::from_json(value).map_err(|error| error.in_field(name)),
+        None => Ok(default()),
+    }
+}
+
+impl FromJson for Value {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Boolean(value) => Ok(*value),
+            _ => Err(FromJsonError::expected("a boolean", value)),
+        }
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(FromJsonError::expected("a string", value)),
+        }
+    }
+}
+
+/// Numbers convert with the checked `TryFrom` conversions on [`Number`](crate::value::Number).
+impl FromJson for f64 {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Number(number) => Ok(number.try_into()?),
+            _ => Err(FromJsonError::expected("a number", value)),
+        }
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Number(number) => Ok(number.try_into()?),
+            _ => Err(FromJsonError::expected("a number", value)),
+        }
+    }
+}
+
+/// Integers convert if they are in range, and floats too if they have no fractional part, as
+/// with `TryFrom`.
+macro_rules! impl_from_json_integer {
+    ($($integer:ty),*) => {$(
+        impl FromJson for $integer {
+            fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+                match value {
+                    Value::Number(number) => Ok(number.try_into()?),
+                    _ => Err(FromJsonError::expected("an integer", value)),
+                }
+            }
+        }
+    )*};
+}
+
+impl_from_json_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Null => Ok(None),
+            _ => T::from_json(value).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(index, element)| {
+                    T::from_json(element).map_err(|error| error.in_element(index))
+                })
+                .collect(),
+            _ => Err(FromJsonError::expected("an array", value)),
+        }
+    }
+}
+
+impl<T: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, T, S> {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Object(object) => object
+                .iter()
+                .map(|(key, member)| {
+                    T::from_json(member)
+                        .map(|member| (key.clone(), member))
+                        .map_err(|error| error.in_field(key))
+                })
+                .collect(),
+            _ => Err(FromJsonError::expected("an object", value)),
+        }
+    }
+}
+
+impl<T: FromJson, const N: usize> FromJson for [T; N] {
+    fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+        match value {
+            Value::Array(array) if array.len() == N => Vec::<T>::from_json(value)
+                .map(|elements| elements.try_into().ok().expect("length was checked")),
+            _ => Err(FromJsonError::expected(&format!("an array of {N}"), value)),
+        }
+    }
+}
+
+/// Tuples convert from arrays of the same length.
+macro_rules! impl_from_json_tuple {
+    ($length:literal => $($element:ident $index:tt),+) => {
+        impl<$($element: FromJson),+> FromJson for ($($element,)+) {
+            fn from_json(value: &Value) -> Result<Self, FromJsonError> {
+                match value {
+                    Value::Array(array) if array.len() == $length => Ok(($(
+                        $element::from_json(&array[$index])
+                            .map_err(|error| error.in_element($index))?,
+                    )+)),
+                    _ => Err(FromJsonError::expected(concat!("an array of ", $length), value)),
+                }
+            }
+        }
+    };
+}
+
+impl_from_json_tuple!(1 => A 0);
+impl_from_json_tuple!(2 => A 0, B 1);
+impl_from_json_tuple!(3 => A 0, B 1, C 2);
+impl_from_json_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_from_json_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_from_json_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::JsonParser;
+
+    fn from_json<T: FromJson>(input: &str) -> Result<T, String> {
+        T::from_json(&JsonParser::parse_from_bytes(input.as_bytes()).unwrap())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(from_json::<bool>("true"), Ok(true));
+        assert_eq!(from_json::<String>(r#""a""#), Ok("a".to_string()));
+        assert_eq!(from_json::<f64>("1"), Ok(1.0));
+        assert_eq!(from_json::<f32>("0.5"), Ok(0.5));
+        assert_eq!(from_json::<u8>("255"), Ok(255));
+        assert_eq!(from_json::<i64>("-9223372036854775808"), Ok(i64::MIN));
+        assert_eq!(from_json::<u64>("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(
+            from_json::<u128>("340282366920938463463374607431768211455"),
+            Ok(u128::MAX)
+        );
+        assert_eq!(from_json::<i32>("1e2"), Ok(100));
+        assert_eq!(from_json::<u8>("2.0"), Ok(2));
+
+        assert_eq!(
+            from_json::<u8>("256"),
+            Err("expected u8, found a number out of its range".to_string())
+        );
+        assert_eq!(
+            from_json::<u32>("-1"),
+            Err("expected u32, found a number out of its range".to_string())
+        );
+        assert_eq!(
+            from_json::<i32>("1.5"),
+            Err("expected i32, found a number with a fractional part".to_string())
+        );
+        assert_eq!(
+            from_json::<f32>("1e39"),
+            Err("expected f32, found a number out of its range".to_string())
+        );
+        assert_eq!(
+            from_json::<i32>("null"),
+            Err("expected an integer, found null".to_string())
+        );
+        assert_eq!(
+            from_json::<String>("[]"),
+            Err("expected a string, found an array".to_string())
+        );
+    }
+
+    #[test]
+    fn containers() {
+        assert_eq!(
+            from_json::<Vec<Option<u8>>>("[1, null, 3]"),
+            Ok(vec![Some(1), None, Some(3)])
+        );
+        assert_eq!(
+            from_json::<HashMap<String, Vec<bool>>>(r#"{"a": [true], "b": []}"#),
+            Ok(HashMap::from([
+                ("a".to_string(), vec![true]),
+                ("b".to_string(), vec![])
+            ]))
+        );
+        assert_eq!(
+            from_json::<(u8, String, Option<bool>)>(r#"[1, "two", null]"#),
+            Ok((1, "two".to_string(), None))
+        );
+        assert_eq!(from_json::<[f64; 2]>("[1, 2.5]"), Ok([1.0, 2.5]));
+
+        assert_eq!(
+            from_json::<(u8, u8)>("[1, 2, 3]"),
+            Err("expected an array of 2, found an array".to_string())
+        );
+        assert_eq!(
+            from_json::<[u8; 3]>("[1, 2]"),
+            Err("expected an array of 3, found an array".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_name_the_path() {
+        let error =
+            from_json::<HashMap<String, Vec<(u8, bool)>>>(r#"{"a/b": [[1, true], [2, 3]]}"#);
+        assert_eq!(
+            error,
+            Err("expected a boolean, found a number at /a~1b/1/1".to_string())
+        );
+
+        let value = JsonParser::parse_from_bytes(b"[[1], [true]]").unwrap();
+        let error = Vec::<Vec<u8>>::from_json(&value).unwrap_err();
+        assert_eq!(error.path(), "/1/0");
+        assert_eq!(error.message(), "expected an integer, found a boolean");
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived() {
+        #[derive(FromJson, Debug, PartialEq)]
+        struct Pair {
+            x0: f64,
+            #[json(rename = "y-0")]
+            y0: f64,
+            label: Option<String>,
+            #[json(default)]
+            weight: u32,
+            #[json(default = "default_points")]
+            points: Vec<[i8; 2]>,
+        }
+
+        fn default_points() -> Vec<[i8; 2]> {
+            vec![[0, 0]]
+        }
+
+        #[derive(FromJson, Debug, PartialEq)]
+        struct Pairs<T> {
+            pairs: Vec<T>,
+        }
+
+        assert_eq!(
+            from_json::<Pairs<Pair>>(r#"{"pairs": [{"x0": 1, "y-0": 2, "y0": "ignored"}]}"#),
+            Ok(Pairs {
+                pairs: vec![Pair {
+                    x0: 1.0,
+                    y0: 2.0,
+                    label: None,
+                    weight: 0,
+                    points: vec![[0, 0]],
+                }]
+            })
+        );
+        assert_eq!(
+            from_json::<Pair>(
+                r#"{"x0": 1, "y-0": 2, "label": "a", "weight": 3, "points": [[1, -1]]}"#
+            ),
+            Ok(Pair {
+                x0: 1.0,
+                y0: 2.0,
+                label: Some("a".to_string()),
+                weight: 3,
+                points: vec![[1, -1]],
+            })
+        );
+
+        assert_eq!(
+            from_json::<Pairs<Pair>>(r#"{"pairs": [{"x0": 1, "y-0": 2}, {"x0": 1}]}"#),
+            Err(r#"missing field "y-0" at /pairs/1"#.to_string())
+        );
+        assert_eq!(
+            from_json::<Pairs<Pair>>(r#"{"pairs": [{"x0": 1, "y-0": 2, "points": [[1, 200]]}]}"#),
+            Err("expected i8, found a number out of its range at /pairs/0/points/0/1".to_string())
+        );
+        assert_eq!(
+            from_json::<Pair>("[]"),
+            Err("expected an object, found an array".to_string())
+        );
+    }
+}
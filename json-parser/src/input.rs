@@ -0,0 +1,203 @@
+use crate::error::{ErrorKind, ParseError};
+use crate::value::Number;
+use std::borrow::Cow;
+
+/// A source of JSON bytes that the parser and tokenizer read from.
+///
+/// Implemented by [`crate::scanner::Scanner`] for documents already in memory and by
+/// [`crate::reader::JsonReader`] for anything implementing [`std::io::Read`]. Input is consumed
+/// one byte at a time and only decoded as UTF-8 inside strings, where it has to be validated.
+pub(crate) trait Input<'a> {
+    /// A location in the input, cheap to take and only turned into a
+    /// [`crate::error::Position`] when an error is reported there.
+    type Mark: Copy;
+
+    /// Look at the next byte without consuming it, or `None` at the end of input.
+    fn peek(&mut self) -> Result<Option<u8>, ParseError>;
+
+    /// Consume the byte returned by the last call to [`Input::peek`].
+    ///
+    /// This must not be a line break or part of a multi-byte character, which are only consumed
+    /// by [`Input::skip_whitespace`] and [`Input::parse_string`] so that positions stay accurate.
+    fn discard(&mut self);
+
+    fn mark(&self) -> Self::Mark;
+
+    fn error_at(&self, kind: ErrorKind, mark: Self::Mark) -> ParseError;
+
+    /// Error for the character at the current location, which didn't fit where it appeared.
+    fn unexpected(&mut self) -> ParseError;
+
+    /// Consume the byte returned by the last call to [`Input::peek`], which may be a line break
+    /// or part of a multi-byte character.
+    ///
+    /// Only inputs that keep track of positions as they go need to do anything more than
+    /// [`Input::discard`] here.
+    fn discard_any(&mut self) {
+        self.discard();
+    }
+
+    /// Parse a string delimited by `quote`, starting at the opening one.
+    fn parse_quoted_string(&mut self, quote: u8) -> Result<Cow<'a, str>, ParseError>;
+
+    /// Parse a number, also accepting `-Infinity` if `allow_infinity` is set.
+    fn parse_number(&mut self, allow_infinity: bool) -> Result<Number, ParseError>;
+
+    /// Parse a string starting at its opening quote.
+    fn parse_string(&mut self) -> Result<Cow<'a, str>, ParseError> {
+        self.parse_quoted_string(b'"')
+    }
+
+    fn error(&self, kind: ErrorKind) -> ParseError {
+        self.error_at(kind, self.mark())
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.discard();
+        }
+
+        Ok(())
+    }
+
+    /// Skip a `//` comment up to the end of the line, or a `/* */` comment, starting at its first
+    /// slash.
+    fn skip_comment(&mut self) -> Result<(), ParseError> {
+        let start = self.mark();
+
+        // Skip the first slash.
+        self.discard();
+
+        match self.peek()? {
+            Some(b'/') => {
+                // The line break is left to be skipped as whitespace.
+                while self.peek()?.is_some_and(|byte| byte != b'\n') {
+                    self.discard_any();
+                }
+            }
+            Some(b'*') => {
+                self.discard();
+
+                loop {
+                    match self.peek()? {
+                        Some(b'*') => {
+                            self.discard();
+
+                            if self.peek()? == Some(b'/') {
+                                self.discard();
+                                break;
+                            }
+                        }
+                        Some(_) => self.discard_any(),
+                        None => return Err(self.error_at(ErrorKind::UnterminatedComment, start)),
+                    }
+                }
+            }
+            _ => return Err(self.error_at(ErrorKind::UnexpectedCharacter('/'), start)),
+        }
+
+        Ok(())
+    }
+
+    /// Consume `literal`, failing at the first byte that doesn't match.
+    fn parse_literal(&mut self, literal: &[u8]) -> Result<(), ParseError> {
+        for expected in literal {
+            if self.peek()? != Some(*expected) {
+                return Err(self.error(ErrorKind::InvalidLiteral));
+            }
+
+            self.discard();
+        }
+
+        Ok(())
+    }
+
+    /// Decode the escape sequence at the current location into `buffer`.
+    ///
+    /// `string_start` is the opening quote of the string, used for error reporting, and `quote`
+    /// is that quote, which can only be escaped with `\'` if it's a single quote.
+    fn parse_escape(
+        &mut self,
+        string_start: Self::Mark,
+        quote: u8,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        let escape_start = self.mark();
+
+        // Skip backslash.
+        self.discard();
+
+        let byte = match self.peek()? {
+            Some(b'"') => b'"',
+            Some(b'\'') if quote == b'\'' => b'\'',
+            Some(b'\\') => b'\\',
+            Some(b'/') => b'/',
+            Some(b'b') => 0x08,
+            Some(b'f') => 0x0c,
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b't') => b'\t',
+            Some(b'u') => {
+                self.discard();
+                let character = self.parse_unicode_escape(escape_start)?;
+                buffer.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+
+                return Ok(());
+            }
+            Some(_) => return Err(self.error_at(ErrorKind::InvalidEscape, escape_start)),
+            None => return Err(self.error_at(ErrorKind::UnterminatedString, string_start)),
+        };
+
+        buffer.push(byte);
+        self.discard();
+
+        Ok(())
+    }
+
+    /// Decode a `\uXXXX` escape, and its low surrogate pair if it's a high surrogate, whose `\u`
+    /// has already been consumed.
+    fn parse_unicode_escape(&mut self, escape_start: Self::Mark) -> Result<char, ParseError> {
+        let invalid_unicode =
+            |input: &Self| input.error_at(ErrorKind::InvalidUnicodeEscape, escape_start);
+
+        let code_point = match self.parse_hex_escape()? {
+            Some(high @ 0xD800..=0xDBFF) => {
+                // A high surrogate must be immediately followed by an escaped low surrogate.
+                for expected in b"\\u" {
+                    if self.peek()? != Some(*expected) {
+                        return Err(invalid_unicode(self));
+                    }
+
+                    self.discard();
+                }
+
+                match self.parse_hex_escape()? {
+                    Some(low @ 0xDC00..=0xDFFF) => {
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    _ => return Err(invalid_unicode(self)),
+                }
+            }
+            Some(code_point) => code_point,
+            None => return Err(invalid_unicode(self)),
+        };
+
+        char::from_u32(code_point).ok_or_else(|| invalid_unicode(self))
+    }
+
+    /// Read the 4 hex digits of a `\uXXXX` escape, or `None` if they are malformed.
+    fn parse_hex_escape(&mut self) -> Result<Option<u32>, ParseError> {
+        let mut code_point = 0;
+
+        for _ in 0..4 {
+            let Some(digit) = self.peek()?.and_then(|byte| char::from(byte).to_digit(16)) else {
+                return Ok(None);
+            };
+
+            code_point = code_point * 16 + digit;
+            self.discard();
+        }
+
+        Ok(Some(code_point))
+    }
+}
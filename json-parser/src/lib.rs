@@ -1,7 +1,31 @@
+pub mod borrowed;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod documents;
+pub mod error;
+pub mod events;
+pub mod from_json;
+mod input;
+pub mod map;
+pub mod ndjson;
+mod number;
+pub mod options;
 pub mod parser;
+pub mod pointer;
 pub mod reader;
+mod scanner;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "simd")]
+mod structural;
+#[cfg(feature = "tokenizer")]
 pub mod tokens;
 pub mod value;
+pub mod writer;
+
+// Lets code derived with `FromJson` refer to this crate by name from inside it.
+#[cfg(feature = "derive")]
+extern crate self as json_parser;
 
 #[cfg_attr(feature = "profile", macro_use)]
 extern crate instrument_macros;
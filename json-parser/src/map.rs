@@ -0,0 +1,466 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt::{Debug, Formatter};
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+/// Number of entries up to which lookups just go through them in order, which beats hashing
+/// the key for the small objects that make up most JSON documents.
+const LINEAR_SCAN_LIMIT: usize = 8;
+
+/// The members of a JSON object, in the order they were inserted.
+///
+/// Besides the usual [`Map::insert`], which replaces the value of an existing key in place,
+/// [`Map::append`] adds an entry even if the key is already present so that objects with
+/// duplicate keys can be kept as they are. Lookups with [`Map::get`] then find the last of them,
+/// and [`Map::get_all`] finds all of them.
+///
+/// Two maps are equal if they have the same entries, regardless of their order.
+#[derive(Clone)]
+pub struct Map<K, V> {
+    entries: Vec<(K, V)>,
+    /// Hash index of the entries, built once there are more than [`LINEAR_SCAN_LIMIT`].
+    table: Option<Table>,
+    /// Whether any key appears in more than one entry.
+    has_duplicates: bool,
+}
+
+/// Open-addressing hash table of indexes into the entries, pointing to the last entry for each
+/// key.
+#[derive(Clone)]
+struct Table {
+    /// Index of an entry plus one, or 0 for an empty slot. The length is a power of two.
+    slots: Vec<usize>,
+    hasher: RandomState,
+}
+
+impl Table {
+    /// The slot where probing for `key` starts.
+    fn home_slot<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        // Only the low bits are used, so truncating the hash on 32-bit targets is fine.
+        #[allow(clippy::cast_possible_truncation)]
+        let hash = self.hasher.hash_one(key) as usize;
+
+        hash & (self.slots.len() - 1)
+    }
+}
+
+impl<K, V> Map<K, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            table: None,
+            has_duplicates: false,
+        }
+    }
+
+    /// Number of entries, including those with duplicate keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the entries in insertion order.
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Iterate over the entries in insertion order, with mutable values.
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (&K, &mut V)> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    #[must_use]
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    #[must_use]
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> Map<K, V>
+where
+    K: Hash + Eq,
+{
+    /// The value of the last entry with `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).map(|index| &self.entries[index].1)
+    }
+
+    /// The value of the last entry with `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).map(|index| &mut self.entries[index].1)
+    }
+
+    /// The values of all entries with `key`, in insertion order.
+    pub fn get_all<'m, Q>(&'m self, key: &'m Q) -> impl Iterator<Item = &'m V> + 'm
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // Without duplicates there's at most one entry, which is quicker to look up.
+        let single = if self.has_duplicates {
+            None
+        } else {
+            Some(self.get(key))
+        };
+
+        let all = single.is_none().then(|| {
+            self.entries
+                .iter()
+                .filter(move |(entry, _)| entry.borrow() == key)
+                .map(|(_, value)| value)
+        });
+
+        single
+            .flatten()
+            .into_iter()
+            .chain(all.into_iter().flatten())
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Set the value for `key`, returning the previous one.
+    ///
+    /// An existing entry keeps its position, and if there are several, only the last one is
+    /// replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.find(&key) {
+            return Some(std::mem::replace(&mut self.entries[index].1, value));
+        }
+
+        self.push(key, value);
+
+        None
+    }
+
+    /// Add an entry at the end, even if there already is one with the same key.
+    pub fn append(&mut self, key: K, value: V) {
+        if self.contains_key(&key) {
+            self.has_duplicates = true;
+        }
+
+        self.push(key, value);
+    }
+
+    /// Remove all entries with `key`, shifting the following ones back, and return the value of
+    /// the last of them.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key)?;
+
+        let mut removed = None;
+        let mut entries = Vec::with_capacity(self.entries.len() - 1);
+
+        for (entry, value) in self.entries.drain(..) {
+            if entry.borrow() == key {
+                removed = Some(value);
+            } else {
+                entries.push((entry, value));
+            }
+        }
+
+        self.entries = entries;
+        self.table = None;
+        self.build_table();
+
+        removed
+    }
+
+    /// Index of the last entry with `key`.
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(table) = &self.table else {
+            return self
+                .entries
+                .iter()
+                .rposition(|(entry, _)| entry.borrow() == key);
+        };
+
+        let mask = table.slots.len() - 1;
+        let mut slot = table.home_slot(key);
+
+        loop {
+            match table.slots[slot] {
+                0 => return None,
+                index if self.entries[index - 1].0.borrow() == key => return Some(index - 1),
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+    }
+
+    fn push(&mut self, key: K, value: V) {
+        self.entries.push((key, value));
+
+        match &self.table {
+            Some(table) if self.entries.len() * 2 <= table.slots.len() => {
+                self.index(self.entries.len() - 1);
+            }
+            _ => self.build_table(),
+        }
+    }
+
+    /// Build the hash table from scratch, or drop it if there are few enough entries to scan.
+    fn build_table(&mut self) {
+        if self.entries.len() <= LINEAR_SCAN_LIMIT {
+            self.table = None;
+            return;
+        }
+
+        let hasher = self
+            .table
+            .take()
+            .map_or_else(RandomState::new, |table| table.hasher);
+
+        self.table = Some(Table {
+            slots: vec![0; (self.entries.len() * 2).next_power_of_two()],
+            hasher,
+        });
+
+        for index in 0..self.entries.len() {
+            self.index(index);
+        }
+    }
+
+    /// Point the slot for the key of entry `index` to it.
+    fn index(&mut self, index: usize) {
+        let Some(table) = &mut self.table else {
+            return;
+        };
+
+        let key = &self.entries[index].0;
+        let mask = table.slots.len() - 1;
+        let mut slot = table.home_slot(key);
+
+        loop {
+            match table.slots[slot] {
+                0 => break,
+                other if self.entries[other - 1].0 == *key => break,
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+
+        table.slots[slot] = index + 1;
+    }
+}
+
+impl<K, V> Default for Map<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Debug for Map<K, V>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> PartialEq for Map<K, V>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        if !self.has_duplicates && !other.has_duplicates {
+            return self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value));
+        }
+
+        self.keys()
+            .all(|key| self.get_all(key).eq(other.get_all(key)))
+    }
+}
+
+impl<K, V> Eq for Map<K, V>
+where
+    K: Hash + Eq,
+    V: Eq,
+{
+}
+
+impl<K, Q, V> Index<&Q> for Map<K, V>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = V;
+
+    /// The value of the last entry with `key`, panicking if there is none.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("Key is not in the map")
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Map<K, V>
+where
+    K: Hash + Eq,
+{
+    /// Insert every entry in turn, so the last value for each key wins.
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<K, V> Extend<(K, V)> for Map<K, V>
+where
+    K: Hash + Eq,
+{
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for Map<K, V>
+where
+    K: Hash + Eq,
+{
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<K, V> IntoIterator for Map<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m Map<K, V> {
+    type Item = (&'m K, &'m V);
+    type IntoIter = std::iter::Map<std::slice::Iter<'m, (K, V)>, fn(&'m (K, V)) -> (&'m K, &'m V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_insertion_order() {
+        for count in [3, LINEAR_SCAN_LIMIT, 100] {
+            let keys = (0..count)
+                .rev()
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>();
+
+            let mut map = Map::new();
+
+            for (value, key) in keys.iter().enumerate() {
+                assert_eq!(map.insert(key.clone(), value), None);
+            }
+
+            assert_eq!(map.insert(keys[1].clone(), 1000), Some(1));
+            assert_eq!(
+                map.keys().collect::<Vec<_>>(),
+                keys.iter().collect::<Vec<_>>()
+            );
+            assert_eq!(map.len(), count);
+            assert_eq!(map[keys[1].as_str()], 1000);
+            assert_eq!(map.get(keys[count - 1].as_str()), Some(&(count - 1)));
+            assert_eq!(map.get("missing"), None);
+        }
+    }
+
+    #[test]
+    fn duplicates() {
+        for count in [2, 50] {
+            let mut map = (0..count)
+                .map(|key| (key.to_string(), key))
+                .collect::<Map<_, _>>();
+
+            map.append("0".to_string(), 100);
+            map.append("1".to_string(), 101);
+            map.append("0".to_string(), 200);
+
+            assert_eq!(map.len(), count + 3);
+            assert_eq!(map.get("0"), Some(&200));
+            assert_eq!(map.get_all("0").collect::<Vec<_>>(), vec![&0, &100, &200]);
+            assert_eq!(map.get_all("1").collect::<Vec<_>>(), vec![&1, &101]);
+
+            assert_eq!(map.insert("0".to_string(), 300), Some(200));
+            assert_eq!(map.get_all("0").collect::<Vec<_>>(), vec![&0, &100, &300]);
+
+            assert_eq!(map.remove("0"), Some(300));
+            assert_eq!(map.len(), count);
+            assert_eq!(map.get("0"), None);
+            assert_eq!(map.get("1"), Some(&101));
+            assert_eq!(map.keys().next().map(String::as_str), Some("1"));
+        }
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let map = Map::from([("a", 1), ("b", 2)]);
+
+        assert_eq!(map, Map::from([("b", 2), ("a", 1)]));
+        assert_ne!(map, Map::from([("a", 1), ("b", 3)]));
+        assert_ne!(map, Map::from([("a", 1)]));
+
+        let mut duplicates = Map::from([("a", 1)]);
+        duplicates.append("a", 2);
+
+        let mut other = Map::from([("a", 2)]);
+        other.append("a", 1);
+
+        assert_ne!(duplicates, other);
+        assert_ne!(duplicates, Map::from([("a", 2), ("b", 2)]));
+    }
+}
@@ -0,0 +1,83 @@
+use instrument::cpu_timer::estimate_cpu_frequency;
+use instrument::page_faults::get_page_size;
+use instrument::repetition::RepetitionTester;
+use json_parser::parser::JsonParser;
+use memmap::Mmap;
+use std::fs::File;
+
+/// Compares parsing a JSON file mapped into memory with reading it into a `Vec` first, e.g. for
+/// one generated with `haversine_input uniform 123 1000000`.
+///
+/// The first two tests take the parser out of the picture, to show what copying the file costs
+/// against faulting in the pages of the mapping.
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("Usage: mmap_throughput <path to JSON file>");
+    let file_size = std::fs::metadata(&path).unwrap().len();
+    let page_size = usize::try_from(get_page_size()).unwrap();
+    let cpu_frequency = estimate_cpu_frequency();
+
+    {
+        println!("Read into Vec");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            let input = std::fs::read(&path).unwrap();
+            repetition_tester.end();
+
+            assert_eq!(input.len() as u64, file_size);
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+
+    {
+        println!("Map and touch every page");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            let file = File::open(&path).unwrap();
+            // SAFETY: The benchmark owns the file, and nothing truncates or modifies it while it
+            // is mapped.
+            let mapping = unsafe { Mmap::map(&file) }.unwrap();
+            let sum = mapping
+                .iter()
+                .step_by(page_size)
+                .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            repetition_tester.end();
+
+            std::hint::black_box(sum);
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+
+    {
+        println!("Read into Vec + parse from bytes");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            let input = std::fs::read(&path).unwrap();
+            let _ = JsonParser::parse_from_bytes(&input).unwrap();
+            repetition_tester.end();
+
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+
+    {
+        println!("Parse mmap");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            repetition_tester.begin();
+            // SAFETY: As above, nothing modifies the file while it is mapped.
+            let _ = unsafe { JsonParser::parse_mmap(&path) }.unwrap();
+            repetition_tester.end();
+
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::options::ParserOptions;
+use crate::value::Value;
+use crate::writer::{self, Format};
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// An iterator over newline-delimited JSON (NDJSON, or JSON Lines) records read from any
+/// [`Read`], one value per line.
+///
+/// Lines that are empty or only hold whitespace are skipped, and a `\r` before the line break is
+/// ignored. Errors are reported at their line and column in the whole input, and parsing carries
+/// on with the next line, except after I/O errors which end the iteration.
+///
+/// The maximum document size in the options applies to each line.
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::ndjson::NdjsonReader;
+///
+/// let input: &[u8] = b"{\"a\": 1}\n\n[2, 3]\n{\"a\" 4}\n";
+/// let records: Vec<_> = NdjsonReader::new(input).collect();
+///
+/// assert_eq!(records.len(), 3);
+/// assert_eq!(records[2].as_ref().unwrap_err().line(), 4);
+/// ```
+pub struct NdjsonReader<R> {
+    reader: BufReader<R>,
+    options: ParserOptions,
+    /// Reused for the contents of each line.
+    buffer: Vec<u8>,
+    /// 1-based number of the last line read.
+    line: usize,
+    /// Offset of the first byte of the last line read.
+    line_start: usize,
+    /// Number of bytes read so far.
+    consumed: usize,
+    /// Whether the end of input or an I/O error was reached.
+    done: bool,
+}
+
+impl<R> NdjsonReader<R>
+where
+    R: Read,
+{
+    /// Create a new [`NdjsonReader`] that reads records from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// Create a new [`NdjsonReader`] that parses each record according to `options`.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            options,
+            buffer: Vec::new(),
+            line: 0,
+            line_start: 0,
+            consumed: 0,
+            done: false,
+        }
+    }
+
+    /// 1-based number of the line the last record was read from.
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Read the next line into `buffer`, without its line break, returning whether there was one.
+    ///
+    /// Lines are only read up to just past the maximum document size, so that parsing them fails,
+    /// and the rest is skipped.
+    fn read_line(&mut self) -> io::Result<bool> {
+        let limit = self
+            .options
+            .max_document_size
+            .map_or(u64::MAX, |size| size as u64 + 1);
+
+        self.buffer.clear();
+        self.line_start = self.consumed;
+
+        let length = (&mut self.reader)
+            .take(limit)
+            .read_until(b'\n', &mut self.buffer)?;
+
+        if length == 0 {
+            return Ok(false);
+        }
+
+        self.consumed += length;
+        self.line += 1;
+
+        if self.buffer.last() == Some(&b'\n') {
+            self.buffer.pop();
+
+            if self.buffer.last() == Some(&b'\r') {
+                self.buffer.pop();
+            }
+        } else {
+            self.skip_line()?;
+        }
+
+        Ok(true)
+    }
+
+    /// Skip the rest of a line that was cut off, including its line break.
+    fn skip_line(&mut self) -> io::Result<()> {
+        loop {
+            let available = self.reader.fill_buf()?;
+
+            let (length, found) = match available.iter().position(|byte| *byte == b'\n') {
+                Some(index) => (index + 1, true),
+                None => (available.len(), available.is_empty()),
+            };
+
+            self.reader.consume(length);
+            self.consumed += length;
+
+            if found {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Move `error`, reported for the current line on its own, to its position in the input.
+    fn locate(&self, error: &ParseError) -> ParseError {
+        let position = error.position();
+
+        ParseError::new(
+            error.kind().clone(),
+            Position {
+                offset: self.line_start + position.offset,
+                line: self.line,
+                column: position.column,
+            },
+        )
+    }
+}
+
+impl<R> Iterator for NdjsonReader<R>
+where
+    R: Read,
+{
+    type Item = Result<Value, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+
+                    let position = Position {
+                        offset: self.consumed,
+                        line: self.line + 1,
+                        column: 1,
+                    };
+
+                    return Some(Err(ParseError::new(ErrorKind::Io(error.kind()), position)));
+                }
+            }
+
+            if self.done || self.buffer.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            return Some(
+                self.options
+                    .parse_from_bytes(&self.buffer)
+                    .map_err(|error| self.locate(&error)),
+            );
+        }
+
+        None
+    }
+}
+
+/// Writes values as newline-delimited JSON, each on its own line in compact form.
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::ndjson::NdjsonWriter;
+/// use json_parser::value::Value;
+///
+/// let mut writer = NdjsonWriter::new(Vec::new());
+///
+/// writer.write(&Value::Boolean(true)).unwrap();
+/// writer.write(&Value::String("two\nlines".to_string())).unwrap();
+///
+/// assert_eq!(writer.into_inner(), b"true\n\"two\\nlines\"\n");
+/// ```
+pub struct NdjsonWriter<W> {
+    writer: W,
+}
+
+impl<W> NdjsonWriter<W>
+where
+    W: Write,
+{
+    /// Create a new [`NdjsonWriter`] that writes to `writer`, which is best buffered.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Write `value` followed by a line break.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error the underlying writer returned.
+    pub fn write(&mut self, value: &Value) -> io::Result<()> {
+        writer::write_value_io(&mut self.writer, value, Format::Compact)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error the underlying writer returned.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::JsonParser;
+    use crate::value::Number;
+
+    #[test]
+    fn records_round_trip() {
+        let values = [
+            r#"{"x0":1.5,"y0":-2.25,"nested":{"a":[1,"two\nlines"]}}"#,
+            "null",
+            "[]",
+            r#""string""#,
+        ]
+        .map(|input| JsonParser::parse_from_bytes(input.as_bytes()).unwrap());
+
+        let mut writer = NdjsonWriter::new(Vec::new());
+
+        for value in &values {
+            writer.write(value).unwrap();
+        }
+
+        let output = writer.into_inner();
+        assert_eq!(output.split(|byte| *byte == b'\n').count(), 5);
+
+        let records: Vec<_> = NdjsonReader::new(&output[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, values);
+    }
+
+    #[test]
+    fn lines_are_skipped_and_counted() {
+        let input: &[u8] = b"\n  1\r\n\t\n2\n\n   \n3";
+        let mut reader = NdjsonReader::new(input);
+        let mut lines = Vec::new();
+
+        while let Some(record) = reader.next() {
+            lines.push((record.unwrap().to_string(), reader.line()));
+        }
+
+        assert_eq!(
+            lines,
+            [
+                ("1".to_string(), 2),
+                ("2".to_string(), 4),
+                ("3".to_string(), 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_per_line() {
+        let input: &[u8] = b"[1]\n{\"a\": }\n[2, \n[3] [4]\n\xc3\xa9\n[5]";
+        let records: Vec<_> = NdjsonReader::new(input)
+            .map(|record| match record {
+                Ok(value) => Ok(value.to_string()),
+                Err(error) => Err((
+                    error.kind().clone(),
+                    error.offset(),
+                    error.line(),
+                    error.column(),
+                )),
+            })
+            .collect();
+
+        assert_eq!(
+            records,
+            [
+                Ok("[1]".to_string()),
+                Err((ErrorKind::UnexpectedCharacter('}'), 10, 2, 7)),
+                Err((ErrorKind::UnexpectedEndOfInput, 16, 3, 5)),
+                Err((ErrorKind::TrailingCharacters, 21, 4, 5)),
+                Err((ErrorKind::UnexpectedCharacter('é'), 25, 5, 1)),
+                Ok("[5]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_lines_are_limited() {
+        let input = format!("[1]\n[{}1]\n[2]\n", "1, ".repeat(10_000));
+        let records: Vec<_> = NdjsonReader::with_options(
+            input.as_bytes(),
+            ParserOptions::new().max_document_size(100),
+        )
+        .collect();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0],
+            Ok(Value::Array(vec![Value::Number(Number::I64(1))]))
+        );
+
+        let error = records[1].as_ref().unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DocumentTooLarge);
+        assert_eq!(
+            (error.offset(), error.line(), error.column()),
+            (104, 2, 101)
+        );
+
+        assert!(records[2].is_ok());
+    }
+}
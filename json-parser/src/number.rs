@@ -0,0 +1,285 @@
+use crate::error::{ErrorKind, ParseError};
+use crate::input::Input;
+use crate::value::Number;
+use std::str::from_utf8;
+
+/// Consume a JSON number from `input` and convert it into a [`Number`], validating it against the
+/// RFC 8259 number grammar:
+///
+/// ```text
+/// number = [ "-" ] int [ frac ] [ exp ]
+/// int    = "0" / ( digit1-9 *DIGIT )
+/// frac   = "." 1*DIGIT
+/// exp    = ( "e" / "E" ) [ "-" / "+" ] 1*DIGIT
+/// ```
+///
+/// Integers are stored as [`Number::I64`] when they fit, [`Number::U64`] when they are positive
+/// and fit, and as [`Number::Raw`] otherwise so that they round-trip without loss. Numbers with a
+/// fraction or exponent are stored as [`Number::F64`], and so is `-0`, like serde_json does, since
+/// neither integer type can hold its sign. With the `arbitrary-precision` feature enabled, floats
+/// that f64 can't represent exactly are kept as [`Number::Raw`] instead.
+///
+/// If `allow_infinity` is set, `-Infinity` is also accepted as negative infinity.
+///
+/// The text of the number is collected in `text`, which is cleared first. Grammar violations are
+/// reported at the offending byte, and floats out of f64 range at the start of the number.
+pub(crate) fn parse_number<'a, I: Input<'a>>(
+    input: &mut I,
+    text: &mut Vec<u8>,
+    allow_infinity: bool,
+) -> Result<Number, ParseError> {
+    let start = input.mark();
+    text.clear();
+
+    if input.peek()? == Some(b'-') {
+        take(input, text, b'-');
+
+        if allow_infinity && input.peek()? == Some(b'I') {
+            input.parse_literal(b"Infinity")?;
+            return Ok(Number::F64(f64::NEG_INFINITY));
+        }
+    }
+
+    match input.peek()? {
+        Some(b'0') => take(input, text, b'0'),
+        Some(digit @ b'1'..=b'9') => {
+            take(input, text, digit);
+            take_digits(input, text)?;
+        }
+        _ => return Err(input.error(ErrorKind::InvalidNumber)),
+    }
+
+    let mut is_integer = true;
+
+    if input.peek()? == Some(b'.') {
+        is_integer = false;
+        take(input, text, b'.');
+        take_required_digits(input, text)?;
+    }
+
+    if let Some(exponent @ (b'e' | b'E')) = input.peek()? {
+        is_integer = false;
+        take(input, text, exponent);
+
+        if let Some(sign @ (b'+' | b'-')) = input.peek()? {
+            take(input, text, sign);
+        }
+
+        take_required_digits(input, text)?;
+    }
+
+    // Catch things like `01`, `1.2.3` or `2x` here rather than as a confusing error later on.
+    let continues = |byte: u8| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'+' | b'-');
+
+    if input.peek()?.is_some_and(continues) {
+        return Err(input.error(ErrorKind::InvalidNumber));
+    }
+
+    // The grammar only admits ASCII.
+    let text = from_utf8(text).map_err(|_| input.error_at(ErrorKind::InvalidNumber, start))?;
+
+    if is_integer {
+        Ok(integer_from_text(text))
+    } else {
+        float_from_text(text).ok_or_else(|| input.error_at(ErrorKind::InvalidNumber, start))
+    }
+}
+
+fn take<'a, I: Input<'a>>(input: &mut I, text: &mut Vec<u8>, byte: u8) {
+    text.push(byte);
+    input.discard();
+}
+
+fn take_digits<'a, I: Input<'a>>(input: &mut I, text: &mut Vec<u8>) -> Result<(), ParseError> {
+    while let Some(digit @ b'0'..=b'9') = input.peek()? {
+        take(input, text, digit);
+    }
+
+    Ok(())
+}
+
+fn take_required_digits<'a, I: Input<'a>>(
+    input: &mut I,
+    text: &mut Vec<u8>,
+) -> Result<(), ParseError> {
+    match input.peek()? {
+        Some(b'0'..=b'9') => take_digits(input, text),
+        _ => Err(input.error(ErrorKind::InvalidNumber)),
+    }
+}
+
+fn integer_from_text(text: &str) -> Number {
+    if text == "-0" {
+        Number::F64(-0.0)
+    } else if let Ok(number) = text.parse::<i64>() {
+        Number::I64(number)
+    } else if let Ok(number) = text.parse::<u64>() {
+        Number::U64(number)
+    } else {
+        Number::Raw(text.to_string())
+    }
+}
+
+fn float_from_text(text: &str) -> Option<Number> {
+    let number = text.parse::<f64>().ok()?;
+
+    if cfg!(feature = "arbitrary-precision") && !is_lossless(text, number) {
+        return Some(Number::Raw(text.to_string()));
+    }
+
+    // Values beyond f64 range would silently become infinity.
+    number.is_finite().then_some(Number::F64(number))
+}
+
+/// Whether `number` has exactly the same significant digits and magnitude as `text`, i.e. whether
+/// writing it back out would reproduce the original value.
+fn is_lossless(text: &str, number: f64) -> bool {
+    number.is_finite() && normalized_decimal(text) == normalized_decimal(&format!("{number:e}"))
+}
+
+/// Split a decimal number into its significant digits, without leading or trailing zeros, and the
+/// position of the decimal point relative to the first of those digits.
+fn normalized_decimal(text: &str) -> (String, i64) {
+    let text = text.trim_start_matches('-');
+    let (mantissa, exponent) = text
+        .split_once(['e', 'E'])
+        .map_or((text, 0), |(mantissa, exponent)| {
+            (mantissa, exponent.parse::<i64>().unwrap_or(0))
+        });
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = format!("{integer}{fraction}");
+    let significant = digits.trim_start_matches('0');
+
+    #[allow(clippy::cast_possible_wrap)]
+    let point = integer.len() as i64 - (digits.len() - significant.len()) as i64 + exponent;
+    let significant = significant.trim_end_matches('0');
+
+    if significant.is_empty() {
+        (String::new(), 0)
+    } else {
+        (significant.to_string(), point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::from_json::FromJson;
+    use crate::parser::JsonParser;
+    use crate::scanner::Scanner;
+
+    /// Parse `text` as a number, returning the byte offset of the error on failure.
+    fn parse_number(text: &str) -> Result<Number, usize> {
+        super::parse_number(&mut Scanner::new(text.as_bytes()), &mut Vec::new(), false)
+            .map_err(|error| error.offset())
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse_number("0"), Ok(Number::I64(0)));
+        assert!(
+            matches!(parse_number("-0"), Ok(Number::F64(zero)) if zero == 0.0 && zero.is_sign_negative())
+        );
+        assert_eq!(parse_number("-42"), Ok(Number::I64(-42)));
+        assert_eq!(
+            parse_number("9223372036854775807"),
+            Ok(Number::I64(i64::MAX))
+        );
+        assert_eq!(
+            parse_number("-9223372036854775808"),
+            Ok(Number::I64(i64::MIN))
+        );
+        assert_eq!(
+            parse_number("18446744073709551615"),
+            Ok(Number::U64(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn negative_zero_keeps_its_sign() {
+        let value = JsonParser::parse_from_bytes(b"[0, -0, 0.0, -0.0]").unwrap();
+        assert_eq!(value.to_string(), "[0,-0.0,0.0,-0.0]");
+
+        // It is still an integer with no fractional part.
+        let zero = JsonParser::parse_from_bytes(b"-0").unwrap();
+        assert_eq!(i32::from_json(&zero), Ok(0));
+        assert_eq!(u64::from_json(&zero), Ok(0));
+
+        #[cfg(feature = "serde")]
+        {
+            assert_eq!(crate::de::from_slice::<i64>(b"-0"), Ok(0));
+            assert_eq!(crate::de::from_slice::<u8>(b"-0"), Ok(0));
+        }
+    }
+
+    #[test]
+    fn integers_beyond_64_bits_are_lossless() {
+        assert_eq!(
+            parse_number("18446744073709551616"),
+            Ok(Number::Raw("18446744073709551616".to_string()))
+        );
+        assert_eq!(
+            parse_number("-9223372036854775809"),
+            Ok(Number::Raw("-9223372036854775809".to_string()))
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(parse_number("1.5"), Ok(Number::F64(1.5)));
+        assert_eq!(parse_number("-0.25"), Ok(Number::F64(-0.25)));
+        assert_eq!(parse_number("1e5"), Ok(Number::F64(100_000.)));
+        assert_eq!(parse_number("1E+5"), Ok(Number::F64(100_000.)));
+        assert_eq!(parse_number("25e-2"), Ok(Number::F64(0.25)));
+        assert_eq!(parse_number("1.50e2"), Ok(Number::F64(150.)));
+        assert_eq!(parse_number("0.0e0"), Ok(Number::F64(0.)));
+        assert_eq!(
+            parse_number("95.26235434764715"),
+            Ok(Number::F64(95.262_354_347_647_15))
+        );
+    }
+
+    #[cfg(not(feature = "arbitrary-precision"))]
+    #[test]
+    fn high_precision_floats_are_rounded() {
+        assert_eq!(
+            parse_number("0.1000000000000000000000000001"),
+            Ok(Number::F64(0.1))
+        );
+        assert_eq!(parse_number("1e400"), Err(0));
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn high_precision_floats_are_lossless() {
+        assert_eq!(
+            parse_number("0.1000000000000000000000000001"),
+            Ok(Number::Raw("0.1000000000000000000000000001".to_string()))
+        );
+        assert_eq!(
+            parse_number("3.14159265358979323846264338327950288"),
+            Ok(Number::Raw(
+                "3.14159265358979323846264338327950288".to_string()
+            ))
+        );
+        assert_eq!(parse_number("1e400"), Ok(Number::Raw("1e400".to_string())));
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert_eq!(parse_number(""), Err(0));
+        assert_eq!(parse_number("-"), Err(1));
+        assert_eq!(parse_number("--1"), Err(1));
+        assert_eq!(parse_number("01"), Err(1));
+        assert_eq!(parse_number("-01"), Err(2));
+        assert_eq!(parse_number("1."), Err(2));
+        assert_eq!(parse_number(".5"), Err(0));
+        assert_eq!(parse_number("1.2.3"), Err(3));
+        assert_eq!(parse_number("1e"), Err(2));
+        assert_eq!(parse_number("1e+"), Err(3));
+        assert_eq!(parse_number("1e5.0"), Err(3));
+        assert_eq!(parse_number("1-2"), Err(1));
+        assert_eq!(parse_number("2x"), Err(1));
+    }
+}
@@ -0,0 +1,473 @@
+use crate::borrowed;
+use crate::documents::Documents;
+use crate::error::ParseError;
+use crate::parser::JsonParser;
+use crate::reader::JsonReader;
+use crate::value::Value;
+use std::io::Read;
+use std::path::Path;
+
+/// What to do when a key appears more than once in the same object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Fail with [`crate::error::ErrorKind::DuplicateKey`] at the repeated key.
+    Error,
+    /// Keep the value of the first occurrence.
+    FirstWins,
+    /// Keep the value of the last occurrence, in the position of the first.
+    #[default]
+    LastWins,
+    /// Keep every occurrence as a separate entry, see [`crate::map::Map::get_all`].
+    CollectAll,
+}
+
+/// Default for [`ParserOptions::max_depth`], far below where the recursive parser would run out
+/// of stack.
+const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Configuration for parsing JSON, which has the same entry points as [`JsonParser`].
+///
+/// Besides the duplicate key policy, this sets limits for parsing input from untrusted sources,
+/// and enables lenient modes that accept common extensions to JSON. Only the nesting depth is
+/// limited by default, and nothing but standard JSON is accepted.
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::options::{DuplicateKeys, ParserOptions};
+///
+/// let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+///
+/// assert!(options.parse_from_bytes(br#"{"a": 1, "a": 2}"#).is_err());
+///
+/// let options = ParserOptions::new()
+///     .max_document_size(1024 * 1024)
+///     .allow_comments(true)
+///     .allow_trailing_commas(true);
+///
+/// assert!(options.parse_from_bytes(b"[1, 2, /* three */ 3,]").is_ok());
+/// ```
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ParserOptions {
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) max_depth: usize,
+    pub(crate) max_string_length: Option<usize>,
+    pub(crate) max_document_size: Option<usize>,
+    pub(crate) max_elements: Option<usize>,
+    pub(crate) allow_comments: bool,
+    pub(crate) allow_trailing_commas: bool,
+    pub(crate) allow_single_quotes: bool,
+    pub(crate) allow_nan_infinity: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_keys: DuplicateKeys::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_string_length: None,
+            max_document_size: None,
+            max_elements: None,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            allow_single_quotes: false,
+            allow_nan_infinity: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Options for standard JSON, which are also what [`JsonParser`] uses.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set what to do when a key appears more than once in the same object, which by default is
+    /// to keep the last value.
+    #[must_use]
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Fail with [`crate::error::ErrorKind::NestingTooDeep`] at arrays and objects nested in
+    /// `depth` others, which is 128 by default. Scalars can still be nested `depth` deep, and a
+    /// depth of 0 only accepts scalars.
+    ///
+    /// The parser recurses once for each level, so raising this far enough will overflow the
+    /// stack on hostile input.
+    #[must_use]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Fail with [`crate::error::ErrorKind::StringTooLong`] at strings, keys included, longer
+    /// than `length` bytes once escapes are decoded.
+    #[must_use]
+    pub fn max_string_length(mut self, length: usize) -> Self {
+        self.max_string_length = Some(length);
+        self
+    }
+
+    /// Fail with [`crate::error::ErrorKind::DocumentTooLarge`] at the first byte of input past
+    /// `size`.
+    ///
+    /// Input from a [`Read`] is only read up to the limit, while slices are rejected before
+    /// parsing starts.
+    #[must_use]
+    pub fn max_document_size(mut self, size: usize) -> Self {
+        self.max_document_size = Some(size);
+        self
+    }
+
+    /// Fail with [`crate::error::ErrorKind::TooManyElements`] at the first element of an array
+    /// past `count`, or the first key of an object once it has `count` members.
+    #[must_use]
+    pub fn max_elements(mut self, count: usize) -> Self {
+        self.max_elements = Some(count);
+        self
+    }
+
+    /// Accept `// line` and `/* block */` comments wherever whitespace is allowed.
+    #[must_use]
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Accept a comma after the last element of an array or member of an object.
+    #[must_use]
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.allow_trailing_commas = allow;
+        self
+    }
+
+    /// Accept strings and keys in single quotes, in which `\'` is a valid escape and `"` needs
+    /// none.
+    #[must_use]
+    pub fn allow_single_quotes(mut self, allow: bool) -> Self {
+        self.allow_single_quotes = allow;
+        self
+    }
+
+    /// Accept `NaN`, `Infinity` and `-Infinity` as floats. These are written back as `null`, as
+    /// JSON can't represent them.
+    #[must_use]
+    pub fn allow_nan_infinity(mut self, allow: bool) -> Self {
+        self.allow_nan_infinity = allow;
+        self
+    }
+
+    /// Parse JSON from bytes, like [`JsonParser::parse_from_bytes`].
+    pub fn parse_from_bytes(&self, input: &[u8]) -> Result<Value, ParseError> {
+        JsonParser::parse_slice(input, self)
+    }
+
+    /// Parse JSON from bytes into a [`borrowed::Value`], like [`JsonParser::parse_borrowed`].
+    pub fn parse_borrowed<'a>(&self, input: &'a [u8]) -> Result<borrowed::Value<'a>, ParseError> {
+        JsonParser::parse_slice(input, self)
+    }
+
+    /// Parse JSON from any [`Read`], like [`JsonParser::parse`].
+    pub fn parse<R: Read>(&self, reader: R) -> Result<Value, ParseError> {
+        JsonParser::parse_document(
+            &mut JsonReader::new(reader).with_max_size(self.max_document_size),
+            self,
+        )
+    }
+
+    /// Parse the JSON file at `path` by mapping it into memory, like [`JsonParser::parse_mmap`].
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated until this returns, as with
+    /// [`JsonParser::parse_mmap`].
+    pub unsafe fn parse_mmap<P: AsRef<Path>>(&self, path: P) -> Result<Value, ParseError> {
+        // SAFETY: Forwarded to the caller.
+        unsafe { JsonParser::parse_mapped(path.as_ref(), self) }
+    }
+
+    /// Parse a stream of concatenated JSON documents from any [`Read`], like
+    /// [`JsonParser::parse_documents`].
+    pub fn parse_documents<R: Read>(&self, reader: R) -> Documents<R> {
+        Documents::new(reader, self.clone())
+    }
+
+    /// Deserialize a `T` from bytes, like [`crate::de::from_slice`].
+    #[cfg(feature = "serde")]
+    pub fn deserialize_from_bytes<'de, T: serde::Deserialize<'de>>(
+        &self,
+        input: &'de [u8],
+    ) -> Result<T, ParseError> {
+        crate::de::deserialize_slice(input, self)
+    }
+
+    /// Deserialize a `T` from any [`Read`], like [`crate::de::from_reader`].
+    #[cfg(feature = "serde")]
+    pub fn deserialize<R: Read, T: serde::de::DeserializeOwned>(
+        &self,
+        reader: R,
+    ) -> Result<T, ParseError> {
+        crate::de::deserialize_reader(reader, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::value::Number;
+
+    const DUPLICATES: &[u8] = br#"{"a": 1, "b": 2, "a": 3, "c": {"a": 4, "a": 5}}"#;
+
+    fn parse(policy: DuplicateKeys) -> Result<Value, ParseError> {
+        ParserOptions::new()
+            .duplicate_keys(policy)
+            .parse_from_bytes(DUPLICATES)
+    }
+
+    #[test]
+    fn keys_keep_their_order() {
+        let input = br#"{"z": 1, "a": {"y": 2, "b": 3}, "m": 4}"#;
+        let value = JsonParser::parse_from_bytes(input).unwrap();
+
+        let Value::Object(object) = &value else {
+            panic!("Expected an object, got {value:?}");
+        };
+
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["z", "a", "m"]);
+        assert_eq!(value.to_string(), r#"{"z":1,"a":{"y":2,"b":3},"m":4}"#);
+    }
+
+    #[test]
+    fn duplicates_error() {
+        let error = parse(DuplicateKeys::Error).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DuplicateKey("a".to_string()));
+        assert_eq!(error.offset(), 17);
+
+        let error = ParserOptions::new()
+            .duplicate_keys(DuplicateKeys::Error)
+            .parse_borrowed(br#"[{"a": 1}, {"b": 1, "b": 2}]"#)
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DuplicateKey("b".to_string()));
+        assert_eq!(error.offset(), 20);
+    }
+
+    #[test]
+    fn duplicates_first_and_last_win() {
+        for (policy, a, nested) in [
+            (DuplicateKeys::FirstWins, 1, 4),
+            (DuplicateKeys::LastWins, 3, 5),
+        ] {
+            let value = parse(policy).unwrap();
+
+            assert_eq!(
+                value.to_string(),
+                format!(r#"{{"a":{a},"b":2,"c":{{"a":{nested}}}}}"#)
+            );
+        }
+    }
+
+    #[test]
+    fn duplicates_collect_all() {
+        let value = parse(DuplicateKeys::CollectAll).unwrap();
+
+        let Value::Object(object) = &value else {
+            panic!("Expected an object, got {value:?}");
+        };
+
+        assert_eq!(object.len(), 4);
+        assert_eq!(
+            object.get_all("a").collect::<Vec<_>>(),
+            [
+                &Value::Number(Number::I64(1)),
+                &Value::Number(Number::I64(3))
+            ]
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"{"a":1,"b":2,"a":3,"c":{"a":4,"a":5}}"#
+        );
+
+        let borrowed = ParserOptions::new()
+            .duplicate_keys(DuplicateKeys::CollectAll)
+            .parse_borrowed(DUPLICATES)
+            .unwrap();
+
+        assert_eq!(borrowed.into_owned(), value);
+    }
+
+    /// Parse `input` both from a slice and from a reader, which must agree.
+    fn parse_both(options: &ParserOptions, input: &str) -> Result<Value, ParseError> {
+        let value = options.parse_from_bytes(input.as_bytes());
+
+        // Compared as text, as NaN isn't equal to itself.
+        assert_eq!(
+            format!("{:?}", options.parse(input.as_bytes())),
+            format!("{value:?}"),
+            "Reading {input:?}"
+        );
+
+        value
+    }
+
+    fn parse_error(options: &ParserOptions, input: &str) -> (ErrorKind, usize) {
+        let error = parse_both(options, input).unwrap_err();
+        (error.kind().clone(), error.offset())
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let options = ParserOptions::new().max_depth(2);
+
+        assert!(parse_both(&options, r#"[{"a": 1}, []]"#).is_ok());
+        assert_eq!(
+            parse_error(&options, r#"[{"a": [1]}]"#),
+            (ErrorKind::NestingTooDeep, 7)
+        );
+        assert_eq!(
+            parse_error(&ParserOptions::new().max_depth(0), "[]"),
+            (ErrorKind::NestingTooDeep, 0)
+        );
+
+        // Deep enough to overflow the stack without the default limit.
+        let deep = "[".repeat(1_000_000);
+        assert_eq!(
+            parse_error(&ParserOptions::new(), &deep),
+            (ErrorKind::NestingTooDeep, 128)
+        );
+
+        let deep = format!("{}1{}", "[".repeat(128), "]".repeat(128));
+        assert!(parse_both(&ParserOptions::new(), &deep).is_ok());
+    }
+
+    #[test]
+    fn sizes_are_limited() {
+        let options = ParserOptions::new().max_string_length(3);
+
+        assert!(parse_both(&options, r#"{"abc": "\u00e9"}"#).is_ok());
+        assert_eq!(
+            parse_error(&options, r#"["abc", "abcd"]"#),
+            (ErrorKind::StringTooLong, 8)
+        );
+        assert_eq!(
+            parse_error(&options, r#"{"abcd": 1}"#),
+            (ErrorKind::StringTooLong, 1)
+        );
+
+        let options = ParserOptions::new().max_elements(2);
+
+        assert!(parse_both(&options, r#"[[1, 2], {"a": 1, "b": 2}]"#).is_ok());
+        assert_eq!(
+            parse_error(&options, "[1, 2,  3]"),
+            (ErrorKind::TooManyElements, 8)
+        );
+        assert_eq!(
+            parse_error(&options, r#"{"a": 1, "b": 2, "c": 3}"#),
+            (ErrorKind::TooManyElements, 17)
+        );
+
+        let options = ParserOptions::new().max_document_size(6);
+
+        assert!(parse_both(&options, "[1, 2]").is_ok());
+        assert_eq!(
+            parse_error(&options, "[1, 2] "),
+            (ErrorKind::DocumentTooLarge, 6)
+        );
+
+        // Readers stop at the limit, even in the middle of a chunk.
+        let input = format!("[{}1]", "1, ".repeat(100_000));
+        let error = ParserOptions::new()
+            .max_document_size(100_000)
+            .parse(input.as_bytes())
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DocumentTooLarge);
+        assert_eq!(error.offset(), 100_000);
+    }
+
+    #[test]
+    fn lenient_modes_are_opt_in() {
+        let inputs = [
+            "[1, // one\n 2 /* two */]",
+            r#"{"a": [1, 2,],}"#,
+            r#"{'a': 'it\'s "quoted"'}"#,
+            "[NaN, Infinity, -Infinity]",
+        ];
+
+        for input in inputs {
+            assert!(
+                parse_both(&ParserOptions::new(), input).is_err(),
+                "Parsed {input:?}"
+            );
+        }
+
+        let options = ParserOptions::new()
+            .allow_comments(true)
+            .allow_trailing_commas(true)
+            .allow_single_quotes(true)
+            .allow_nan_infinity(true);
+
+        let values: Vec<_> = inputs
+            .iter()
+            .map(|input| parse_both(&options, input).unwrap().to_string())
+            .collect();
+
+        assert_eq!(
+            values,
+            [
+                "[1,2]",
+                r#"{"a":[1,2]}"#,
+                r#"{"a":"it's \"quoted\""}"#,
+                "[null,null,null]"
+            ]
+        );
+
+        let Value::Array(array) = parse_both(&options, "[NaN, Infinity, -Infinity]").unwrap()
+        else {
+            panic!("Expected an array");
+        };
+        let floats: Vec<_> = array
+            .iter()
+            .map(|value| match value {
+                Value::Number(Number::F64(float)) => *float,
+                _ => panic!("Expected a float, got {value:?}"),
+            })
+            .collect();
+
+        assert!(floats[0].is_nan());
+        assert_eq!(floats[1..], [f64::INFINITY, f64::NEG_INFINITY]);
+    }
+
+    #[test]
+    fn lenient_errors() {
+        let options = ParserOptions::new()
+            .allow_comments(true)
+            .allow_trailing_commas(true)
+            .allow_single_quotes(true);
+
+        assert_eq!(
+            parse_error(&options, "[1, /* two"),
+            (ErrorKind::UnterminatedComment, 4)
+        );
+        assert_eq!(
+            parse_error(&options, "[1 / 2]"),
+            (ErrorKind::UnexpectedCharacter('/'), 3)
+        );
+        assert_eq!(
+            parse_error(&options, "[1,,]"),
+            (ErrorKind::UnexpectedCharacter(','), 3)
+        );
+        assert_eq!(
+            parse_error(&options, r#"["\'"]"#),
+            (ErrorKind::InvalidEscape, 2)
+        );
+
+        // Lines and characters are still counted inside comments.
+        let error = parse_both(&options, "// é\n/* é\n */ é").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('é'));
+        assert_eq!((error.line(), error.column()), (3, 5));
+    }
+}
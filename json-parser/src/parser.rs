@@ -1,102 +1,324 @@
-use crate::tokens::{JsonTokenizer, Token};
-use crate::value::Value;
-use std::collections::HashMap;
+use crate::borrowed;
+use crate::documents::Documents;
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::input::Input;
+use crate::map::Map;
+use crate::options::{DuplicateKeys, ParserOptions};
+use crate::reader::JsonReader;
+use crate::scanner::Scanner;
+#[cfg(feature = "simd")]
+use crate::structural::IndexedScanner;
+use crate::value::{Number, Value};
+use memmap::Mmap;
+use std::borrow::{Borrow, Cow};
 use std::fs::File;
-use std::io::{BufReader, Cursor};
-use std::iter::Peekable;
-use std::slice::Iter;
+use std::hash::Hash;
+use std::io::{self, Read};
+use std::path::Path;
 
 /// Main parser which is the entrypoint for parsing JSON.
+///
+/// Values are built in a single recursive-descent pass over the input bytes, without an
+/// intermediate token stream. The lexing API in [`crate::tokens`] remains available separately.
+/// Use [`ParserOptions`] to parse with anything but the default options.
 pub struct JsonParser;
 
+/// A JSON value type that the parser can build directly while reading input.
+pub(crate) trait BuildValue<'a>: Sized {
+    type Key: Hash + Eq + Borrow<str>;
+
+    fn key(key: Cow<'a, str>) -> Self::Key;
+    fn from_string(string: Cow<'a, str>) -> Self;
+    fn from_number(number: Number) -> Self;
+    fn from_boolean(boolean: bool) -> Self;
+    fn null() -> Self;
+    fn from_array(array: Vec<Self>) -> Self;
+    fn from_object(object: Map<Self::Key, Self>) -> Self;
+}
+
 impl JsonParser {
-    /// Parse an object from token stream.
-    fn process_object(iterator: &mut Peekable<Iter<Token>>) -> HashMap<String, Value> {
-        let mut is_key = true;
-        let mut current_key: Option<&str> = None;
-        let mut value = HashMap::<String, Value>::new();
-
-        while let Some(token) = iterator.next() {
-            match token {
-                Token::CurlyOpen => {
-                    if let Some(current_key) = current_key {
-                        value.insert(
-                            current_key.to_string(),
-                            Value::Object(Self::process_object(iterator)),
-                        );
-                    }
-                }
-                Token::CurlyClose => {
-                    break;
-                }
-                Token::Quotes | Token::ArrayClose => {}
-                Token::Colon => {
-                    is_key = false;
-                }
-                Token::String(string) => {
-                    if is_key {
-                        current_key = Some(string);
-                    } else if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::String(string.clone()));
-                        current_key = None;
-                    }
-                }
-                Token::Number(number) => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Number(*number));
-                        current_key = None;
-                    }
+    /// Parse a complete document, rejecting anything but whitespace after the top-level value.
+    pub(crate) fn parse_document<'a, I: Input<'a>, V: BuildValue<'a>>(
+        input: &mut I,
+        options: &ParserOptions,
+    ) -> Result<V, ParseError> {
+        let value = Self::parse_value(input, options, 0)?;
+
+        Self::skip_whitespace(input, options)?;
+
+        if input.peek()?.is_some() {
+            return Err(input.error(ErrorKind::TrailingCharacters));
+        }
+
+        Ok(value)
+    }
+
+    /// Parse a complete in-memory document, on top of a structural index if the `simd` feature
+    /// is enabled and the index can make sense of the input.
+    pub(crate) fn parse_slice<'a, V: BuildValue<'a>>(
+        input: &'a [u8],
+        options: &ParserOptions,
+    ) -> Result<V, ParseError> {
+        if let Some(max_size) = options.max_document_size {
+            if input.len() > max_size {
+                return Err(Scanner::new(input).error_at(ErrorKind::DocumentTooLarge, max_size));
+            }
+        }
+
+        // The index only knows about double quotes, and would find structurals inside comments.
+        #[cfg(feature = "simd")]
+        if !options.allow_comments && !options.allow_single_quotes {
+            if let Some(mut scanner) = IndexedScanner::new(input) {
+                return Self::parse_document(&mut scanner, options);
+            }
+        }
+
+        Self::parse_document(&mut Scanner::new(input), options)
+    }
+
+    /// Map the file at `path` into memory and parse the mapping like any other slice.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it's mapped, see
+    /// [`JsonParser::parse_mmap`].
+    pub(crate) unsafe fn parse_mapped(
+        path: &Path,
+        options: &ParserOptions,
+    ) -> Result<Value, ParseError> {
+        let io_error =
+            |error: io::Error| ParseError::new(ErrorKind::Io(error.kind()), Position::default());
+
+        let file = File::open(path).map_err(io_error)?;
+
+        // Empty files can't be mapped.
+        if file.metadata().map_err(io_error)?.len() == 0 {
+            return Self::parse_slice(&[], options);
+        }
+
+        // SAFETY: The caller promises the file isn't modified while mapped. The mapping is only
+        // read while parsing, and the parsed value owns its strings, so it can be unmapped after.
+        let mapping = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+
+        Self::parse_slice(&mapping, options)
+    }
+
+    /// Parse a value nested in `depth` arrays and objects.
+    pub(crate) fn parse_value<'a, I: Input<'a>, V: BuildValue<'a>>(
+        input: &mut I,
+        options: &ParserOptions,
+        depth: usize,
+    ) -> Result<V, ParseError> {
+        Self::skip_whitespace(input, options)?;
+
+        match input.peek()? {
+            Some(b'{' | b'[') if depth >= options.max_depth => {
+                Err(input.error(ErrorKind::NestingTooDeep))
+            }
+            Some(b'{') => Self::process_object(input, options, depth + 1).map(V::from_object),
+            Some(b'[') => Self::process_array(input, options, depth + 1).map(V::from_array),
+            Some(b'"') => Self::parse_string(input, options, b'"').map(V::from_string),
+            Some(b'\'') if options.allow_single_quotes => {
+                Self::parse_string(input, options, b'\'').map(V::from_string)
+            }
+            Some(b'-' | b'0'..=b'9') => input
+                .parse_number(options.allow_nan_infinity)
+                .map(V::from_number),
+            Some(b't') => input.parse_literal(b"true").map(|()| V::from_boolean(true)),
+            Some(b'f') => input
+                .parse_literal(b"false")
+                .map(|()| V::from_boolean(false)),
+            Some(b'n') => input.parse_literal(b"null").map(|()| V::null()),
+            Some(b'N') if options.allow_nan_infinity => input
+                .parse_literal(b"NaN")
+                .map(|()| V::from_number(Number::F64(f64::NAN))),
+            Some(b'I') if options.allow_nan_infinity => input
+                .parse_literal(b"Infinity")
+                .map(|()| V::from_number(Number::F64(f64::INFINITY))),
+            _ => Err(input.unexpected()),
+        }
+    }
+
+    /// Skip whitespace, and comments if they are allowed.
+    pub(crate) fn skip_whitespace<'a, I: Input<'a>>(
+        input: &mut I,
+        options: &ParserOptions,
+    ) -> Result<(), ParseError> {
+        input.skip_whitespace()?;
+
+        while options.allow_comments && input.peek()? == Some(b'/') {
+            input.skip_comment()?;
+            input.skip_whitespace()?;
+        }
+
+        Ok(())
+    }
+
+    /// Parse a string delimited by `quote`, checking it against the maximum length.
+    pub(crate) fn parse_string<'a, I: Input<'a>>(
+        input: &mut I,
+        options: &ParserOptions,
+        quote: u8,
+    ) -> Result<Cow<'a, str>, ParseError> {
+        let start = input.mark();
+        let string = input.parse_quoted_string(quote)?;
+
+        if options
+            .max_string_length
+            .is_some_and(|length| string.len() > length)
+        {
+            return Err(input.error_at(ErrorKind::StringTooLong, start));
+        }
+
+        Ok(string)
+    }
+
+    /// Parse an object starting at its opening bracket, which is `depth` containers deep.
+    fn process_object<'a, I: Input<'a>, V: BuildValue<'a>>(
+        input: &mut I,
+        options: &ParserOptions,
+        depth: usize,
+    ) -> Result<Map<V::Key, V>, ParseError> {
+        let start = input.mark();
+        let mut object = Map::new();
+
+        // Skip opening bracket.
+        input.discard();
+        Self::skip_whitespace(input, options)?;
+
+        if input.peek()? == Some(b'}') {
+            input.discard();
+            return Ok(object);
+        }
+
+        loop {
+            Self::skip_whitespace(input, options)?;
+
+            let key_start = input.mark();
+
+            if options
+                .max_elements
+                .is_some_and(|count| object.len() >= count)
+            {
+                return Err(input.error_at(ErrorKind::TooManyElements, key_start));
+            }
+
+            let key = match input.peek()? {
+                Some(b'"') => V::key(Self::parse_string(input, options, b'"')?),
+                Some(b'\'') if options.allow_single_quotes => {
+                    V::key(Self::parse_string(input, options, b'\'')?)
                 }
-                Token::ArrayOpen => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Array(Self::process_array(iterator)));
-                        current_key = None;
-                    }
+                None => return Err(input.error_at(ErrorKind::UnbalancedBrackets, start)),
+                _ => return Err(input.unexpected()),
+            };
+
+            if options.duplicate_keys == DuplicateKeys::Error
+                && object.contains_key::<str>(key.borrow())
+            {
+                let key = key.borrow().to_string();
+                return Err(input.error_at(ErrorKind::DuplicateKey(key), key_start));
+            }
+
+            Self::skip_whitespace(input, options)?;
+
+            match input.peek()? {
+                Some(b':') => input.discard(),
+                None => return Err(input.error_at(ErrorKind::UnbalancedBrackets, start)),
+                _ => return Err(input.unexpected()),
+            }
+
+            let value = Self::parse_value(input, options, depth)?;
+
+            match options.duplicate_keys {
+                DuplicateKeys::FirstWins if object.contains_key::<str>(key.borrow()) => {}
+                DuplicateKeys::CollectAll => object.append(key, value),
+                _ => {
+                    object.insert(key, value);
                 }
-                Token::Comma => is_key = true,
-                Token::Boolean(boolean) => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Boolean(*boolean));
-                        current_key = None;
+            }
+
+            Self::skip_whitespace(input, options)?;
+
+            match input.peek()? {
+                Some(b',') => {
+                    input.discard();
+                    Self::skip_whitespace(input, options)?;
+
+                    if options.allow_trailing_commas && input.peek()? == Some(b'}') {
+                        input.discard();
+                        return Ok(object);
                     }
                 }
-                Token::Null => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Null);
-                        current_key = None;
-                    }
+                Some(b'}') => {
+                    input.discard();
+                    return Ok(object);
                 }
+                Some(b']') => return Err(input.error(ErrorKind::UnbalancedBrackets)),
+                None => return Err(input.error_at(ErrorKind::UnbalancedBrackets, start)),
+                _ => return Err(input.unexpected()),
             }
         }
-
-        value
     }
 
-    /// Parse an array from token stream
+    /// Parse an array starting at its opening bracket, which is `depth` containers deep.
+    ///
+    /// This avoids early returns, which would skip closing the profiler entry.
     #[cfg_attr(feature = "profile", instrument)]
-    fn process_array(iterator: &mut Peekable<Iter<Token>>) -> Vec<Value> {
-        let mut internal_value = Vec::<Value>::new();
+    fn process_array<'a, I: Input<'a>, V: BuildValue<'a>>(
+        input: &mut I,
+        options: &ParserOptions,
+        depth: usize,
+    ) -> Result<Vec<V>, ParseError> {
+        let start = input.mark();
+        let mut array = Vec::new();
+
+        // Skip opening bracket.
+        input.discard();
 
-        while let Some(token) = iterator.next() {
-            match token {
-                Token::CurlyOpen => {
-                    internal_value.push(Value::Object(Self::process_object(iterator)));
+        match Self::skip_whitespace(input, options).and_then(|()| input.peek()) {
+            Ok(Some(b']')) => {
+                input.discard();
+                Ok(array)
+            }
+            Err(error) => Err(error),
+            Ok(_) => loop {
+                if options
+                    .max_elements
+                    .is_some_and(|count| array.len() >= count)
+                {
+                    break Err(input.error(ErrorKind::TooManyElements));
                 }
-                Token::String(string) => internal_value.push(Value::String(string.clone())),
-                Token::Number(number) => internal_value.push(Value::Number(*number)),
-                Token::ArrayOpen => {
-                    internal_value.push(Value::Array(Self::process_array(iterator)));
+
+                match Self::parse_value(input, options, depth) {
+                    Ok(value) => array.push(value),
+                    Err(error) => break Err(error),
                 }
-                Token::ArrayClose => {
-                    break;
+
+                match Self::skip_whitespace(input, options).and_then(|()| input.peek()) {
+                    Ok(Some(b',')) => {
+                        input.discard();
+
+                        // Leave the next element's start for the error above.
+                        match Self::skip_whitespace(input, options).and_then(|()| input.peek()) {
+                            Ok(Some(b']')) if options.allow_trailing_commas => {
+                                input.discard();
+                                break Ok(array);
+                            }
+                            Err(error) => break Err(error),
+                            Ok(_) => {}
+                        }
+                    }
+                    Ok(Some(b']')) => {
+                        input.discard();
+                        break Ok(array);
+                    }
+                    Ok(Some(b'}')) => break Err(input.error(ErrorKind::UnbalancedBrackets)),
+                    Ok(None) => break Err(input.error_at(ErrorKind::UnbalancedBrackets, start)),
+                    Ok(Some(_)) => break Err(input.unexpected()),
+                    Err(error) => break Err(error),
                 }
-                Token::Boolean(boolean) => internal_value.push(Value::Boolean(*boolean)),
-                Token::Null => internal_value.push(Value::Null),
-                Token::Comma | Token::CurlyClose | Token::Quotes | Token::Colon => {}
-            }
+            },
         }
-
-        internal_value
     }
 
     /// Create a new [`JsonParser`] that parses JSON from bytes.
@@ -105,59 +327,338 @@ impl JsonParser {
     ///
     /// * `input`: A slice of bytes from input data.
     ///
-    /// returns: Result<Value, ()>
+    /// returns: Result<Value, ParseError>
     ///
     /// # Examples
     ///
     /// ```
+    /// use json_parser::parser::JsonParser;
+    /// use json_parser::value::Value;
     ///
+    /// let value = JsonParser::parse_from_bytes(br#"[true, null]"#).unwrap();
+    ///
+    /// assert_eq!(value, Value::Array(vec![Value::Boolean(true), Value::Null]));
     /// ```
     #[cfg_attr(feature = "profile", instrument)]
-    pub fn parse_from_bytes<'a>(input: &'a [u8]) -> Result<Value, ()> {
-        let mut json_tokenizer = JsonTokenizer::<BufReader<Cursor<&[u8]>>>::from_bytes(input);
-        let tokens = json_tokenizer.tokenize_json()?;
-
-        Ok(Self::tokens_to_value(tokens))
+    pub fn parse_from_bytes(input: &[u8]) -> Result<Value, ParseError> {
+        Self::parse_slice(input, &ParserOptions::default())
     }
 
+    /// Parse JSON from bytes into a [`borrowed::Value`] that points into `input` instead of
+    /// copying keys and strings out of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::borrowed::Value;
+    /// use json_parser::parser::JsonParser;
+    /// use std::borrow::Cow;
+    ///
+    /// let input = br#"{"key": "value"}"#;
+    /// let value = JsonParser::parse_borrowed(input).unwrap();
+    ///
+    /// if let Value::Object(object) = value {
+    ///     assert!(matches!(object.get("key"), Some(Value::String(Cow::Borrowed("value")))));
+    /// }
+    /// ```
     #[cfg_attr(feature = "profile", instrument)]
-    pub fn parse(reader: File) -> Result<Value, ()> {
-        let mut json_tokenizer = JsonTokenizer::<BufReader<File>>::new(reader);
-        let tokens = json_tokenizer.tokenize_json()?;
+    pub fn parse_borrowed(input: &[u8]) -> Result<borrowed::Value<'_>, ParseError> {
+        Self::parse_slice(input, &ParserOptions::default())
+    }
 
-        Ok(Self::tokens_to_value(tokens))
+    /// Parse JSON from any [`Read`], such as a file, reading it in chunks as parsing goes rather
+    /// than loading it into memory first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::parser::JsonParser;
+    /// use json_parser::value::Value;
+    ///
+    /// let input: &[u8] = br#"[1, "two"]"#;
+    /// let value = JsonParser::parse(input).unwrap();
+    ///
+    /// assert!(matches!(value, Value::Array(array) if array.len() == 2));
+    /// ```
+    #[cfg_attr(feature = "profile", instrument)]
+    pub fn parse<R: Read>(reader: R) -> Result<Value, ParseError> {
+        Self::parse_document(&mut JsonReader::new(reader), &ParserOptions::default())
     }
 
+    /// Parse the JSON file at `path` by mapping it into memory, which saves copying it into a
+    /// buffer first, at the cost of a page fault for every page the parser touches.
+    ///
+    /// Errors opening or mapping the file are reported as [`ErrorKind::Io`] at the start of the
+    /// input.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or any other, until this
+    /// returns. The parser reads the mapped pages directly, so a change in the meantime is
+    /// undefined behaviour, and a truncation can crash the process with `SIGBUS`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::parser::JsonParser;
+    /// use json_parser::value::Value;
+    ///
+    /// let path = std::env::temp_dir().join("parse_mmap_example.json");
+    /// std::fs::write(&path, br#"{"pairs": []}"#).unwrap();
+    ///
+    /// // SAFETY: Nothing else writes to the file.
+    /// let value = unsafe { JsonParser::parse_mmap(&path) }.unwrap();
+    ///
+    /// assert_eq!(value["pairs"], Value::Array(vec![]));
+    /// ```
     #[cfg_attr(feature = "profile", instrument)]
-    fn tokens_to_value(tokens: &[Token]) -> Value {
-        let mut iterator = tokens.iter().peekable();
+    pub unsafe fn parse_mmap<P: AsRef<Path>>(path: P) -> Result<Value, ParseError> {
+        // SAFETY: Forwarded to the caller.
+        unsafe { Self::parse_mapped(path.as_ref(), &ParserOptions::default()) }
+    }
 
-        let mut value = Value::Null;
+    /// Parse a stream of concatenated JSON documents from any [`Read`], one at a time. See
+    /// [`Documents`].
+    pub fn parse_documents<R: Read>(reader: R) -> Documents<R> {
+        Documents::new(reader, ParserOptions::default())
+    }
+}
 
-        while let Some(token) = iterator.next() {
-            match token {
-                Token::CurlyOpen => {
-                    value = Value::Object(Self::process_object(&mut iterator));
-                }
-                Token::String(string) => {
-                    value = Value::String(string.clone());
-                }
-                Token::Number(number) => {
-                    value = Value::Number(*number);
-                }
-                Token::ArrayOpen => {
-                    value = Value::Array(Self::process_array(&mut iterator));
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::value::test::arbitrary_value;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn parse_error(input: &str) -> ParseError {
+        JsonParser::parse_from_bytes(input.as_bytes()).unwrap_err()
+    }
+
+    #[test]
+    fn missing_commas() {
+        let error = parse_error("[1 2]");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('2'));
+        assert_eq!(error.offset(), 3);
+
+        let error = parse_error(r#"{"a": 1 "b": 2}"#);
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('"'));
+        assert_eq!(error.offset(), 8);
+
+        let error = parse_error("[[1][2]]");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('['));
+        assert_eq!(error.offset(), 4);
+    }
+
+    #[test]
+    fn misplaced_colons() {
+        let error = parse_error("[1: 2]");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(':'));
+        assert_eq!(error.offset(), 2);
+
+        let error = parse_error(r#"{"a" 1}"#);
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('1'));
+        assert_eq!(error.offset(), 5);
+
+        let error = parse_error(r#"{"a":: 1}"#);
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(':'));
+        assert_eq!(error.offset(), 5);
+
+        let error = parse_error(r#"{"a": 1, : 2}"#);
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(':'));
+        assert_eq!(error.offset(), 9);
+    }
+
+    #[test]
+    fn misplaced_commas() {
+        let error = parse_error("[1,]");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(']'));
+        assert_eq!(error.offset(), 3);
+
+        let error = parse_error("[,1]");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(','));
+        assert_eq!(error.offset(), 1);
+
+        let error = parse_error(r#"{"a": 1,}"#);
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('}'));
+        assert_eq!(error.offset(), 8);
+    }
+
+    #[test]
+    fn non_string_keys() {
+        let error = parse_error("{1: 2}");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('1'));
+        assert_eq!(error.offset(), 1);
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        let error = parse_error(r#"{"a": [1, 2}"#);
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 11);
+
+        let error = parse_error(r#"{"a": {"b": [1]]"#);
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 15);
+
+        let error = parse_error("[[[]]");
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 0);
+
+        let error = parse_error("[]]");
+        assert_eq!(error.kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(error.offset(), 2);
+    }
+
+    #[test]
+    fn empty_input() {
+        let error = parse_error("");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedEndOfInput);
+        assert_eq!(error.offset(), 0);
+
+        let error = parse_error(" \n ");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedEndOfInput);
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 2);
+    }
+
+    #[test]
+    fn nested_values() {
+        let value =
+            JsonParser::parse_from_bytes(br#" { "a" : [ { } , [ ] , { "b" : [ null ] } ] } "#)
+                .unwrap();
+
+        let Value::Object(object) = value else {
+            panic!("Expected an object, got {value:?}");
+        };
+
+        let Some(Value::Array(array)) = object.get("a") else {
+            panic!("Expected an array, got {object:?}");
+        };
+
+        assert_eq!(array.len(), 3);
+        assert_eq!(array[1], Value::Array(vec![]));
+    }
+
+    #[test]
+    fn mapped_files() {
+        let path = std::env::temp_dir().join(format!("mapped_files_{}.json", std::process::id()));
+
+        let input = br#"{"pairs": [{"x0": 1.5, "y0": -2}], "label": "\u00e9"}"#;
+        std::fs::write(&path, input).unwrap();
+
+        // SAFETY: The file is only written by this test, between parses.
+        let parse_mmap = |path| unsafe { JsonParser::parse_mmap(path) };
+        assert_eq!(parse_mmap(&path), JsonParser::parse_from_bytes(input));
+
+        let options = ParserOptions::new().max_document_size(8);
+        assert_eq!(
+            unsafe { options.parse_mmap(&path) },
+            options.parse_from_bytes(input)
+        );
+
+        std::fs::write(&path, b"").unwrap();
+        let error = parse_mmap(&path).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedEndOfInput);
+
+        std::fs::remove_file(&path).unwrap();
+        let error = parse_mmap(&path).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Io(io::ErrorKind::NotFound));
+        assert_eq!(error.offset(), 0);
+    }
+
+    /// The parsing tests from JSONTestSuite: `y_` files must be accepted, `n_` files rejected,
+    /// and `i_` files may go either way as long as the parser's entry points agree.
+    ///
+    /// The tokenizer only lexes, so it is just checked to accept the valid files, and the
+    /// deserializer doesn't have to agree on `i_` files, where it rejects numbers beyond `f64`.
+    #[test]
+    fn json_test_suite() {
+        let directory = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/testdata/JSONTestSuite/test_parsing"
+        );
+        let mut failures = vec![];
+
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let input = std::fs::read(&path).unwrap();
+
+            let expected = match name.as_bytes()[0] {
+                b'y' => Some(true),
+                b'n' => Some(false),
+                _ => None,
+            };
+
+            let accepted = JsonParser::parse_from_bytes(&input).is_ok();
+            #[cfg_attr(not(any(feature = "tokenizer", feature = "serde")), allow(unused_mut))]
+            let mut results = vec![
+                ("parse_from_bytes", accepted, expected.unwrap_or(accepted)),
+                (
+                    "parse_borrowed",
+                    JsonParser::parse_borrowed(&input).is_ok(),
+                    expected.unwrap_or(accepted),
+                ),
+                (
+                    "parse",
+                    JsonParser::parse(input.as_slice()).is_ok(),
+                    expected.unwrap_or(accepted),
+                ),
+            ];
+
+            #[cfg(feature = "tokenizer")]
+            {
+                let mut tokenizer = crate::tokens::JsonTokenizer::<&[u8]>::from_bytes(&input);
+                let accepted = tokenizer.tokenize_json().is_ok();
+                let expected = expected.filter(|&valid| valid).unwrap_or(accepted);
+                results.push(("tokenize_json", accepted, expected));
+            }
+
+            #[cfg(feature = "serde")]
+            {
+                let accepted = crate::de::from_slice::<Value>(&input).is_ok();
+                results.push(("de::from_slice", accepted, expected.unwrap_or(accepted)));
+            }
+
+            for (entry_point, accepted, expected) in results {
+                if accepted != expected {
+                    failures.push(format!("{entry_point} {name}: accepted {accepted}"));
                 }
-                Token::Boolean(boolean) => value = Value::Boolean(*boolean),
-                Token::Null => value = Value::Null,
-                Token::Comma
-                | Token::CurlyClose
-                | Token::Quotes
-                | Token::Colon
-                | Token::ArrayClose => {}
             }
         }
 
-        value
+        assert!(failures.is_empty(), "{failures:#?}");
+    }
+
+    /// Valid JSON with one byte replaced, which is mostly invalid JSON that still gets past the
+    /// first few bytes.
+    fn corrupted_json() -> impl Strategy<Value = Vec<u8>> {
+        (arbitrary_value(), any::<prop::sample::Index>(), any::<u8>()).prop_map(
+            |(value, index, byte)| {
+                let mut input = value.to_string().into_bytes();
+                let index = index.index(input.len());
+                input[index] = byte;
+                input
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn entry_points_agree(input in prop_oneof![vec(any::<u8>(), 0..64), corrupted_json()]) {
+            let result = JsonParser::parse_from_bytes(&input);
+
+            prop_assert_eq!(JsonParser::parse(input.as_slice()), result.clone());
+            prop_assert_eq!(
+                JsonParser::parse_borrowed(&input).map(borrowed::Value::into_owned),
+                result
+            );
+
+            #[cfg(feature = "tokenizer")]
+            {
+                let _ = crate::tokens::JsonTokenizer::<&[u8]>::from_bytes(&input).tokenize_json();
+            }
+        }
     }
 }
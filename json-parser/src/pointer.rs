@@ -0,0 +1,281 @@
+use crate::value::Value;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Error returned when a JSON Pointer or path query can't be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// A pointer that isn't empty and doesn't start with `/`, or has a `~` that isn't followed by
+    /// `0` or `1`.
+    Malformed(String),
+    /// Nothing at this location, given as a pointer with any wildcards before it resolved.
+    NotFound(String),
+}
+
+impl Display for PointerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PointerError::Malformed(pointer) => write!(f, "malformed JSON pointer {pointer:?}"),
+            PointerError::NotFound(pointer) => write!(f, "no value at {pointer:?}"),
+        }
+    }
+}
+
+impl Error for PointerError {}
+
+/// Split a JSON Pointer into its unescaped reference tokens, or `None` if it's malformed.
+pub(crate) fn tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .map(unescape)
+        .collect()
+}
+
+/// Replace `~1` with `/` and `~0` with `~`, which are the only valid escapes in a token.
+fn unescape(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut characters = token.chars();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '~' => match characters.next()? {
+                '0' => unescaped.push('~'),
+                '1' => unescaped.push('/'),
+                _ => return None,
+            },
+            character => unescaped.push(character),
+        }
+    }
+
+    Some(unescaped)
+}
+
+/// Parse an array index token, which can't have leading zeros or a sign.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+/// Resolve `pointer` against `value`, see [`Value::pointer`].
+pub(crate) fn resolve<'v>(value: &'v Value, pointer: &str) -> Option<&'v Value> {
+    tokens(pointer)?
+        .iter()
+        .try_fold(value, |value, token| match value {
+            Value::Object(object) => object.get(token),
+            Value::Array(array) => array.get(array_index(token)?),
+            _ => None,
+        })
+}
+
+/// Resolve `pointer` against `value` for modification, see [`Value::pointer_mut`].
+pub(crate) fn resolve_mut<'v>(value: &'v mut Value, pointer: &str) -> Option<&'v mut Value> {
+    tokens(pointer)?
+        .iter()
+        .try_fold(value, |value, token| match value {
+            Value::Object(object) => object.get_mut(token),
+            Value::Array(array) => array.get_mut(array_index(token)?),
+            _ => None,
+        })
+}
+
+/// One step taken to reach a value, kept to report where a query failed.
+enum Step<'q> {
+    Key(&'q str),
+    Index(usize),
+}
+
+/// Find every value matching `path` in `value`, see [`Value::query`].
+pub(crate) fn query<'v>(value: &'v Value, path: &str) -> Result<Vec<&'v Value>, PointerError> {
+    let tokens = tokens(path).ok_or_else(|| PointerError::Malformed(path.to_string()))?;
+    let mut matches = Vec::new();
+
+    Query {
+        tokens: &tokens,
+        steps: Vec::new(),
+        matches: &mut matches,
+    }
+    .find(value, 0)?;
+
+    Ok(matches)
+}
+
+struct Query<'q, 'v> {
+    tokens: &'q [String],
+    /// Path to the value being searched.
+    steps: Vec<Step<'q>>,
+    matches: &'q mut Vec<&'v Value>,
+}
+
+impl<'q, 'v: 'q> Query<'q, 'v> {
+    /// Search `value` for the tokens starting at `depth`.
+    fn find(&mut self, value: &'v Value, depth: usize) -> Result<(), PointerError> {
+        let Some(token) = self.tokens.get(depth) else {
+            self.matches.push(value);
+            return Ok(());
+        };
+
+        match value {
+            Value::Array(array) if token == "*" => {
+                for (index, element) in array.iter().enumerate() {
+                    self.step(Step::Index(index), element, depth)?;
+                }
+            }
+            Value::Object(object) if token == "*" => {
+                for (key, member) in object {
+                    self.step(Step::Key(key), member, depth)?;
+                }
+            }
+            Value::Array(array) => match array_index(token) {
+                Some(index) if index < array.len() => {
+                    self.step(Step::Index(index), &array[index], depth)?;
+                }
+                _ => return Err(self.not_found(token)),
+            },
+            Value::Object(object) => match object.get(token) {
+                Some(member) => self.step(Step::Key(token), member, depth)?,
+                None => return Err(self.not_found(token)),
+            },
+            _ => return Err(self.not_found(token)),
+        }
+
+        Ok(())
+    }
+
+    fn step(&mut self, step: Step<'q>, value: &'v Value, depth: usize) -> Result<(), PointerError> {
+        self.steps.push(step);
+        self.find(value, depth + 1)?;
+        self.steps.pop();
+
+        Ok(())
+    }
+
+    /// Error for `token` not matching anything in the value being searched.
+    fn not_found(&self, token: &str) -> PointerError {
+        let mut pointer = String::new();
+
+        for step in &self.steps {
+            match step {
+                Step::Key(key) => push_token(&mut pointer, key),
+                Step::Index(index) => push_token(&mut pointer, &index.to_string()),
+            }
+        }
+
+        push_token(&mut pointer, token);
+
+        PointerError::NotFound(pointer)
+    }
+}
+
+/// Append `token` to `pointer`, escaping it.
+fn push_token(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::JsonParser;
+    use crate::value::Number;
+
+    const DOCUMENT: &[u8] = br#"{
+        "pairs": [{"x0": 1.5, "y0": 2}, {"x0": -3, "y0": 4}],
+        "a/b": {"m~n": [true, null]},
+        "": "empty"
+    }"#;
+
+    #[test]
+    fn pointers() {
+        let value = JsonParser::parse_from_bytes(DOCUMENT).unwrap();
+
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/pairs/1/x0"),
+            Some(&Value::Number(Number::I64(-3)))
+        );
+        assert_eq!(value.pointer("/a~1b/m~0n/0"), Some(&Value::Boolean(true)));
+        assert_eq!(
+            value.pointer("/"),
+            Some(&Value::String("empty".to_string()))
+        );
+
+        for pointer in [
+            "pairs",
+            "/pairs/2",
+            "/pairs/01",
+            "/pairs/-",
+            "/a~2b",
+            "/pairs/0/x0/y",
+        ] {
+            assert_eq!(value.pointer(pointer), None, "Resolved {pointer:?}");
+        }
+    }
+
+    #[test]
+    fn pointers_mut() {
+        let mut value = JsonParser::parse_from_bytes(DOCUMENT).unwrap();
+
+        *value.pointer_mut("/pairs/0/y0").unwrap() = Value::Null;
+        if let Some(Value::Array(array)) = value.pointer_mut("/a~1b/m~0n") {
+            array.push(Value::Boolean(false));
+        }
+
+        assert_eq!(value.pointer("/pairs/0/y0"), Some(&Value::Null));
+        assert_eq!(value.pointer("/a~1b/m~0n/2"), Some(&Value::Boolean(false)));
+        assert!(value.pointer_mut("/pairs/0/z0").is_none());
+    }
+
+    #[test]
+    fn indexing() {
+        let value = JsonParser::parse_from_bytes(DOCUMENT).unwrap();
+
+        assert_eq!(value["pairs"][0]["x0"], Value::Number(Number::F64(1.5)));
+        assert_eq!(value["a/b"]["m~n"][0], Value::Boolean(true));
+        assert_eq!(value["missing"][0]["x0"], Value::Null);
+        assert_eq!(value["pairs"][2], Value::Null);
+        assert_eq!(value[0], Value::Null);
+    }
+
+    #[test]
+    fn queries() {
+        let value = JsonParser::parse_from_bytes(DOCUMENT).unwrap();
+
+        let x0: Vec<f64> = value
+            .query("/pairs/*/x0")
+            .unwrap()
+            .into_iter()
+            .map(|x0| x0.try_into().unwrap())
+            .collect();
+        assert_eq!(x0, [1.5, -3.0]);
+
+        assert_eq!(value.query("/pairs/*/*").unwrap().len(), 4);
+        assert_eq!(value.query("/*").unwrap().len(), 3);
+        assert_eq!(value.query("/a~1b/m~0n/1").unwrap(), [&Value::Null]);
+        assert_eq!(value.query("").unwrap(), [&value]);
+
+        assert_eq!(
+            value.query("/pairs/*/z0"),
+            Err(PointerError::NotFound("/pairs/0/z0".to_string()))
+        );
+        assert_eq!(
+            value.query("/*/m~0n/3"),
+            Err(PointerError::NotFound("/pairs/m~0n".to_string()))
+        );
+        assert_eq!(
+            value.query("/a~1b/*/*/*"),
+            Err(PointerError::NotFound("/a~1b/m~0n/0/*".to_string()))
+        );
+        assert_eq!(
+            value.query("pairs"),
+            Err(PointerError::Malformed("pairs".to_string()))
+        );
+    }
+}
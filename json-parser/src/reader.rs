@@ -1,35 +1,55 @@
-use std::collections::VecDeque;
-use std::io::{BufReader, Cursor, Read, Seek};
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::input::Input;
+use crate::number;
+use crate::value::Number;
+use std::borrow::Cow;
+use std::io::{self, Cursor, Read};
 use std::str::from_utf8;
 
-/// A struct that handles reading input data to be parsed and provides an iterator over said data
-/// character-by-character.
-pub struct JsonReader<T>
-where
-    T: Read + Seek,
-{
-    /// A reference to the input data, which can be anything that implements [`Read`]
-    reader: BufReader<T>,
-    /// A character buffer that holds queue of characters to be used by the iterator.
-    ///
-    /// This is necessary because UTF-8 can be 1-4 bytes long. Because of this, the reader
-    /// always reads 4 bytes at a time. We then iterate over "characters", irrespective of whether
-    /// they are 1 byte long, or 4.
-    ///
-    /// A [`VecDeque`] is used instead of a normal vector because characters need to be read out
-    /// from the start of the buffer.
-    character_buffer: VecDeque<char>,
+/// Default size of the buffer that input is read into.
+const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+/// A buffered reader that feeds JSON to the parser byte-by-byte from anything that implements
+/// [`Read`].
+///
+/// Input is read in large chunks and only decoded as UTF-8 inside strings. Earlier input isn't
+/// kept around to compute positions from when an error is reported, so lines are counted while
+/// skipping whitespace, the only place they can legally start, and multi-byte characters while
+/// reading strings, the only place they can legally appear. Failures of the underlying reader are
+/// reported as [`ErrorKind::Io`] at the position where they occurred.
+pub struct JsonReader<R> {
+    reader: R,
+    buffer: Box<[u8]>,
+    /// Index of the next unread byte in `buffer`.
+    index: usize,
+    /// Number of bytes in `buffer` that hold input.
+    length: usize,
+    /// Number of bytes read before the current contents of `buffer`.
+    consumed: usize,
+    /// 1-based number of the current line.
+    line: usize,
+    /// Offset of the first byte of the current line.
+    line_start: usize,
+    /// Number of UTF-8 continuation bytes consumed since the start of the current line, which
+    /// don't count towards the column.
+    continuation_bytes: usize,
+    /// Reused for the text of numbers.
+    scratch: Vec<u8>,
+    /// Number of bytes that can be read before failing with [`ErrorKind::DocumentTooLarge`].
+    max_size: Option<usize>,
+    /// Whether the input was cut short at `max_size`.
+    truncated: bool,
 }
 
-impl<T> JsonReader<T>
+impl<R> JsonReader<R>
 where
-    T: Read + Seek,
+    R: Read,
 {
-    /// Create a new [`JsonReader`] that reads from a file
+    /// Create a new [`JsonReader`] that reads from `reader`
     ///
     /// # Arguments
     ///
-    /// * `reader`: An instance of buffered reader over file to be parsed.
+    /// * `reader`: Any source of input data, such as a file. It doesn't need to be buffered.
     ///
     /// returns: JsonReader
     ///
@@ -37,33 +57,51 @@ where
     ///
     /// ```
     /// use std::fs::File;
-    /// use std::io::BufReader;
     /// use json_parser::reader::JsonReader;
     ///
     /// let file = File::create("dummy.json").unwrap();
-    /// let reader = BufReader::new(file);
     ///
-    /// let json_reader = JsonReader::new(reader);
+    /// let json_reader = JsonReader::new(file);
     /// ```
-    pub fn new(reader: BufReader<T>) -> Self {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Create a new [`JsonReader`] that reads from `reader` in chunks of up to `capacity` bytes.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
         JsonReader {
             reader,
-            character_buffer: VecDeque::with_capacity(4),
+            buffer: vec![0; capacity.max(1)].into_boxed_slice(),
+            index: 0,
+            length: 0,
+            consumed: 0,
+            line: 1,
+            line_start: 0,
+            continuation_bytes: 0,
+            scratch: Vec::new(),
+            max_size: None,
+            truncated: false,
         }
     }
 
+    /// Fail with [`ErrorKind::DocumentTooLarge`] once more than `max_size` bytes are read.
+    pub(crate) fn with_max_size(mut self, max_size: Option<usize>) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
     /// Create a new [`JsonReader`] that reads from a given byte stream
     ///
     /// # Arguments
     ///
-    /// * `reader`: An instance of buffered reader over input string to be parsed.
+    /// * `bytes`: Input string to be parsed.
     ///
     /// returns: JsonReader
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::io::{BufReader, Cursor};
+    /// use std::io::Cursor;
     /// use json_parser::reader::JsonReader;
     ///
     /// let input_json_string = r#"{"key1":"value1","key2":"value2"}"#;
@@ -72,47 +110,393 @@ where
     /// ```
     #[must_use]
     pub fn from_bytes(bytes: &[u8]) -> JsonReader<Cursor<&[u8]>> {
-        JsonReader {
-            reader: BufReader::new(Cursor::new(bytes)),
-            character_buffer: VecDeque::with_capacity(4),
+        JsonReader::with_capacity(bytes.len(), Cursor::new(bytes))
+    }
+
+    /// Position of the next byte to be read.
+    #[must_use]
+    pub fn position(&self) -> Position {
+        let offset = self.consumed + self.index;
+
+        Position {
+            offset,
+            line: self.line,
+            column: offset - self.line_start - self.continuation_bytes + 1,
         }
     }
+
+    /// Read the next chunk of input once everything in the buffer has been consumed, returning
+    /// how many bytes were read, which is 0 only at the end of input.
+    ///
+    /// Input beyond `max_size` is cut off, and reported once everything before it was consumed.
+    fn fill(&mut self) -> Result<usize, ParseError> {
+        self.consumed += self.length;
+        self.index = 0;
+        self.length = 0;
+
+        if self.truncated {
+            return Err(ParseError::new(
+                ErrorKind::DocumentTooLarge,
+                self.position(),
+            ));
+        }
+
+        self.length = loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(length) => break length,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    return Err(ParseError::new(
+                        ErrorKind::Io(error.kind()),
+                        self.position(),
+                    ));
+                }
+            }
+        };
+
+        if let Some(max_size) = self.max_size {
+            let remaining = max_size.saturating_sub(self.consumed);
+
+            if self.length > remaining {
+                self.length = remaining;
+                self.truncated = true;
+
+                if remaining == 0 {
+                    return Err(ParseError::new(
+                        ErrorKind::DocumentTooLarge,
+                        self.position(),
+                    ));
+                }
+            }
+        }
+
+        Ok(self.length)
+    }
+
+    /// Whether the buffer has unread bytes, reading more input if it doesn't.
+    fn has_input(&mut self) -> Result<bool, ParseError> {
+        Ok(self.index < self.length || self.fill()? > 0)
+    }
+
+    /// Consume the next `length` buffered bytes, which must not contain line breaks.
+    fn consume(&mut self, length: usize) {
+        self.continuation_bytes += self.buffer[self.index..self.index + length]
+            .iter()
+            .filter(|byte| is_continuation(**byte))
+            .count();
+        self.index += length;
+    }
+
+    /// Invalid UTF-8 at the start of the `length` bytes just consumed, which are a character that
+    /// wasn't completed before a byte that can't continue it.
+    fn incomplete_character(&self, length: usize) -> ParseError {
+        let mut position = self.position();
+        position.offset -= length;
+        // Only the first byte of the character counts towards the column.
+        position.column -= 1;
+
+        ParseError::new(ErrorKind::InvalidUtf8, position)
+    }
 }
 
-impl<T> Iterator for JsonReader<T>
+impl<'a, R> Input<'a> for JsonReader<R>
 where
-    T: Read + Seek,
+    R: Read,
 {
-    type Item = char;
+    type Mark = Position;
+
+    fn peek(&mut self) -> Result<Option<u8>, ParseError> {
+        if self.has_input()? {
+            Ok(Some(self.buffer[self.index]))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn discard(&mut self) {
+        self.index += 1;
+    }
 
-    #[allow(clippy::cast_possible_wrap)]
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.character_buffer.is_empty() {
-            return self.character_buffer.pop_front();
+    fn discard_any(&mut self) {
+        if self.buffer[self.index] == b'\n' {
+            self.index += 1;
+            self.line += 1;
+            self.line_start = self.consumed + self.index;
+            self.continuation_bytes = 0;
+        } else {
+            self.consume(1);
         }
+    }
 
-        let mut utf8_buffer = [0, 0, 0, 0];
-        let _ = self.reader.read(&mut utf8_buffer);
+    fn mark(&self) -> Position {
+        self.position()
+    }
 
-        match from_utf8(&utf8_buffer) {
-            Ok(string) => {
-                self.character_buffer = string.chars().collect();
-                self.character_buffer.pop_front()
+    fn error_at(&self, kind: ErrorKind, position: Position) -> ParseError {
+        ParseError::new(kind, position)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        while self.has_input()? {
+            match self.buffer[self.index] {
+                b' ' | b'\t' | b'\r' => self.index += 1,
+                b'\n' => {
+                    self.index += 1;
+                    self.line += 1;
+                    self.line_start = self.consumed + self.index;
+                    self.continuation_bytes = 0;
+                }
+                _ => break,
             }
-            Err(error) => {
-                // Read valid bytes, and rewind the buf reader for the remaining bytes
-                // so that they can be read again in the next iteration.
+        }
 
-                let valid_bytes = error.valid_up_to();
-                let string = from_utf8(&utf8_buffer[..valid_bytes]).unwrap();
+        Ok(())
+    }
 
-                let remaining_bytes = 4 - valid_bytes;
+    fn unexpected(&mut self) -> ParseError {
+        let position = self.position();
+        let mut bytes = Vec::with_capacity(4);
 
-                let _ = self.reader.seek_relative(-(remaining_bytes as i64));
+        // Nothing is parsed after this, so the character can be consumed even if it was split
+        // across two reads.
+        loop {
+            match self.peek() {
+                Ok(Some(byte))
+                    if bytes.is_empty() || (bytes.len() < 4 && is_continuation(byte)) =>
+                {
+                    bytes.push(byte);
+                    self.discard();
+                }
+                Ok(_) => break,
+                Err(error) => return error,
+            }
+        }
+
+        let kind = match String::from_utf8_lossy(&bytes).chars().next() {
+            Some(character) => ErrorKind::UnexpectedCharacter(character),
+            None => ErrorKind::UnexpectedEndOfInput,
+        };
 
-                self.character_buffer = string.chars().collect();
-                self.character_buffer.pop_front()
+        ParseError::new(kind, position)
+    }
+
+    /// Always returns an owned string, as the input buffer is reused between reads.
+    fn parse_quoted_string(&mut self, quote: u8) -> Result<Cow<'a, str>, ParseError> {
+        let start = self.position();
+        let mut string = Vec::new();
+        // Length of the prefix of `string` known to be valid UTF-8.
+        let mut validated = 0;
+
+        let ends_run = |byte: u8| byte == quote || matches!(byte, b'\\' | 0x00..=0x1f);
+
+        // Skip opening quote.
+        self.discard();
+
+        loop {
+            if !self.has_input()? {
+                if validated != string.len() {
+                    return Err(self.incomplete_character(string.len() - validated));
+                }
+
+                return Err(ParseError::new(ErrorKind::UnterminatedString, start));
+            }
+
+            // Copy everything up to the next byte that needs special handling in one go.
+            let run = &self.buffer[self.index..self.length];
+            let length = run
+                .iter()
+                .position(|byte| ends_run(*byte))
+                .unwrap_or(run.len());
+            let run_start = string.len();
+
+            string.extend_from_slice(&run[..length]);
+
+            match from_utf8(&string[validated..]) {
+                Ok(_) => validated = string.len(),
+                // A character split across two reads is completed by the next run.
+                Err(error) if error.error_len().is_none() => validated += error.valid_up_to(),
+                Err(error) => {
+                    let invalid = validated + error.valid_up_to();
+                    let mut position = self.position();
+
+                    if let Some(before) = invalid.checked_sub(run_start) {
+                        position.offset += before;
+                        position.column += run[..before]
+                            .iter()
+                            .filter(|byte| !is_continuation(**byte))
+                            .count();
+                    }
+
+                    return Err(ParseError::new(ErrorKind::InvalidUtf8, position));
+                }
+            }
+
+            self.consume(length);
+
+            match self.peek()? {
+                // Whatever ends the run also ends a character that was still incomplete.
+                Some(byte) if ends_run(byte) && validated != string.len() => {
+                    return Err(self.incomplete_character(string.len() - validated));
+                }
+                Some(byte) if byte == quote => {
+                    self.discard();
+
+                    // Validated above.
+                    return String::from_utf8(string)
+                        .map(Cow::Owned)
+                        .map_err(|_| self.error(ErrorKind::InvalidUtf8));
+                }
+                Some(b'\\') => {
+                    self.parse_escape(start, quote, &mut string)?;
+
+                    // Escapes decode to valid UTF-8.
+                    validated = string.len();
+                }
+                Some(byte @ 0x00..=0x1f) => {
+                    return Err(self.error(ErrorKind::ControlCharacterInString(char::from(byte))));
+                }
+                // Only the end of the buffer was reached.
+                _ => {}
+            }
+        }
+    }
+
+    fn parse_number(&mut self, allow_infinity: bool) -> Result<Number, ParseError> {
+        let mut text = std::mem::take(&mut self.scratch);
+        let number = number::parse_number(self, &mut text, allow_infinity);
+        self.scratch = text;
+
+        number
+    }
+}
+
+/// Whether `byte` continues a multi-byte UTF-8 character, rather than starting a new one.
+fn is_continuation(byte: u8) -> bool {
+    (byte & 0b1100_0000) == 0b1000_0000
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::JsonParser;
+
+    /// A reader that hands out at most `chunk` bytes per read, and then fails with `error` if set.
+    struct Chunked<'a> {
+        input: &'a [u8],
+        chunk: usize,
+        error: Option<io::ErrorKind>,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.input.is_empty() {
+                if let Some(kind) = self.error {
+                    return Err(io::Error::from(kind));
+                }
+            }
+
+            let length = self.chunk.min(buffer.len()).min(self.input.len());
+            buffer[..length].copy_from_slice(&self.input[..length]);
+            self.input = &self.input[length..];
+
+            Ok(length)
+        }
+    }
+
+    fn chunked(input: &[u8], chunk: usize) -> Chunked<'_> {
+        Chunked {
+            input,
+            chunk,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn matches_in_memory_parser() {
+        let input = r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377},{"sample":"string \"sample\" \u00e9\ud834\udd1e ü","nullable":null,"flags":[true,false],"ࠄࠀ":"ࠆࠄ"}]}"#;
+        let expected = JsonParser::parse_from_bytes(input.as_bytes()).unwrap();
+
+        for chunk in 1..8 {
+            let value = JsonParser::parse(chunked(input.as_bytes(), chunk)).unwrap();
+            assert_eq!(value, expected, "Reading {chunk} bytes at a time");
+        }
+    }
+
+    #[test]
+    fn errors_match_in_memory_parser() {
+        let inputs: [&[u8]; 12] = [
+            b"{\n  \"a\": [1, 2}\n}",
+            "[\"ü\", ü]".as_bytes(),
+            b"[1, 2",
+            b"[1] [2]",
+            b"[\n  01]",
+            br#"["\uD834x"]"#,
+            b"[\"ab\xffc\"]",
+            "[\"üü\n\"]".as_bytes(),
+            b"[\"\xc3\xbc\xe2\x82\"]",
+            b"[\"\\n\xc3\\n\"]",
+            b"[\"\xff\n\"]",
+            b"[\"a\xe2\x82",
+        ];
+
+        for input in inputs {
+            let expected = JsonParser::parse_from_bytes(input).unwrap_err();
+
+            for chunk in [1, 2, 64] {
+                let error = JsonParser::parse(chunked(input, chunk)).unwrap_err();
+                assert_eq!(error, expected, "Reading {chunk} bytes at a time");
             }
         }
     }
+
+    #[test]
+    fn invalid_utf8_split_across_reads() {
+        let error = JsonParser::parse(chunked(b"[\"a\xc3\"]", 1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUtf8);
+
+        let error = JsonParser::parse(chunked(b"[\"a\xc3\\n\"]", 1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUtf8);
+    }
+
+    #[test]
+    fn io_errors_are_reported() {
+        let reader = Chunked {
+            input: b"{\"a\": [1,\n 2",
+            chunk: 4,
+            error: Some(io::ErrorKind::ConnectionReset),
+        };
+
+        let error = JsonParser::parse(reader).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Io(io::ErrorKind::ConnectionReset));
+        assert_eq!(error.offset(), 12);
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 3);
+    }
+
+    #[test]
+    fn interrupted_reads_are_retried() {
+        struct Interrupting<'a> {
+            input: &'a [u8],
+            interrupted: bool,
+        }
+
+        impl Read for Interrupting<'_> {
+            fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+                self.interrupted = !self.interrupted;
+
+                if self.interrupted {
+                    return Err(io::Error::from(io::ErrorKind::Interrupted));
+                }
+
+                self.input.read(buffer)
+            }
+        }
+
+        let reader = Interrupting {
+            input: b"[true]",
+            interrupted: false,
+        };
+
+        assert!(JsonParser::parse(reader).is_ok());
+    }
 }
@@ -0,0 +1,82 @@
+use instrument::cpu_timer::estimate_cpu_frequency;
+use instrument::repetition::RepetitionTester;
+use json_parser::events::EventReader;
+use json_parser::parser::JsonParser;
+use json_parser::tokens::JsonTokenizer;
+use std::fs::File;
+use std::io::Read;
+
+/// Measures how fast the input layer gets through a JSON file, e.g. one generated with
+/// `haversine_input uniform 123 1000000`.
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("Usage: reader_throughput <path to JSON file>");
+    let file_size = std::fs::metadata(&path).unwrap().len();
+    let cpu_frequency = estimate_cpu_frequency();
+
+    {
+        println!("Tokenize from file");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            let mut tokenizer = JsonTokenizer::new(File::open(&path).unwrap());
+
+            repetition_tester.begin();
+            let _ = tokenizer.tokenize_json().unwrap();
+            repetition_tester.end();
+
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+
+    {
+        println!("Parse from file");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            let file = File::open(&path).unwrap();
+
+            repetition_tester.begin();
+            let _ = JsonParser::parse(file).unwrap();
+            repetition_tester.end();
+
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+
+    {
+        println!("Stream pairs from file");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            let mut events = EventReader::new(File::open(&path).unwrap());
+
+            repetition_tester.begin();
+            for pair in events.elements("/pairs").unwrap().unwrap() {
+                pair.unwrap();
+            }
+            events.finish().unwrap();
+            repetition_tester.end();
+
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+
+    {
+        println!("Read + parse from bytes");
+        let mut repetition_tester = RepetitionTester::new(file_size, cpu_frequency, Some(10));
+
+        while repetition_tester.loop_test() {
+            let mut file = File::open(&path).unwrap();
+            let mut input = Vec::new();
+
+            repetition_tester.begin();
+            file.read_to_end(&mut input).unwrap();
+            let _ = JsonParser::parse_from_bytes(&input).unwrap();
+            repetition_tester.end();
+
+            repetition_tester.count_bytes(file_size);
+        }
+    }
+}
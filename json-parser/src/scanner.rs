@@ -0,0 +1,163 @@
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::input::Input;
+use crate::number;
+use crate::value::Number;
+use std::borrow::Cow;
+use std::str::from_utf8;
+
+/// A cursor over an in-memory JSON document that reads tokens straight from the input bytes.
+///
+/// Strings without escape sequences are returned as slices of the input, so parsing them doesn't
+/// allocate. Only the byte offset is tracked while scanning; line and column are computed from it
+/// when an error is reported.
+pub(crate) struct Scanner<'a> {
+    input: &'a [u8],
+    index: usize,
+    /// Reused for the text of numbers.
+    scratch: Vec<u8>,
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            index: 0,
+            scratch: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    pub(crate) fn input(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// Move to `offset`, which must be at the start of a token or at the end of input.
+    #[cfg(feature = "simd")]
+    pub(crate) fn seek(&mut self, offset: usize) {
+        self.index = offset;
+    }
+
+    /// Compute the line and column of a byte offset in the input.
+    fn position_at(&self, offset: usize) -> Position {
+        let mut position = Position::default();
+        let before = &self.input[..offset.min(self.input.len())];
+
+        let mut line_start = 0;
+
+        for (index, byte) in before.iter().enumerate() {
+            if *byte == b'\n' {
+                position.line += 1;
+                line_start = index + 1;
+            }
+        }
+
+        // Count characters rather than bytes, skipping UTF-8 continuation bytes.
+        position.column += before[line_start..]
+            .iter()
+            .filter(|byte| (**byte & 0b1100_0000) != 0b1000_0000)
+            .count();
+        position.offset = offset;
+
+        position
+    }
+
+    pub(crate) fn validate_utf8(&self, start: usize, end: usize) -> Result<&'a str, ParseError> {
+        from_utf8(&self.input[start..end])
+            .map_err(|error| self.error_at(ErrorKind::InvalidUtf8, start + error.valid_up_to()))
+    }
+}
+
+impl<'a> Input<'a> for Scanner<'a> {
+    type Mark = usize;
+
+    fn peek(&mut self) -> Result<Option<u8>, ParseError> {
+        Ok(self.input.get(self.index).copied())
+    }
+
+    fn discard(&mut self) {
+        self.index += 1;
+    }
+
+    fn mark(&self) -> usize {
+        self.index
+    }
+
+    fn error_at(&self, kind: ErrorKind, offset: usize) -> ParseError {
+        ParseError::new(kind, self.position_at(offset))
+    }
+
+    fn unexpected(&mut self) -> ParseError {
+        match self.input.get(self.index..) {
+            Some([]) | None => self.error(ErrorKind::UnexpectedEndOfInput),
+            Some(rest) => {
+                let character = String::from_utf8_lossy(&rest[..rest.len().min(4)])
+                    .chars()
+                    .next()
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+                self.error(ErrorKind::UnexpectedCharacter(character))
+            }
+        }
+    }
+
+    /// Returns a slice of the input if the string has no escape sequences, and an owned, decoded
+    /// copy otherwise.
+    ///
+    /// Each run of bytes between escapes is validated as a whole before looking at what ends it,
+    /// so invalid UTF-8 is reported at the first byte that is wrong, whatever follows.
+    fn parse_quoted_string(&mut self, quote: u8) -> Result<Cow<'a, str>, ParseError> {
+        let start = self.index;
+        let mut buffer: Option<Vec<u8>> = None;
+
+        // Skip opening quote.
+        self.index += 1;
+
+        loop {
+            let run_start = self.index;
+
+            while let Some(&byte) = self.input.get(self.index) {
+                if byte == quote || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+
+                self.index += 1;
+            }
+
+            let run = self.validate_utf8(run_start, self.index)?;
+
+            match self.input.get(self.index) {
+                Some(&byte) if byte == quote => {
+                    self.index += 1;
+
+                    let Some(mut buffer) = buffer else {
+                        return Ok(Cow::Borrowed(run));
+                    };
+
+                    buffer.extend_from_slice(run.as_bytes());
+
+                    // Runs are validated above, and escapes decode to valid UTF-8.
+                    return String::from_utf8(buffer)
+                        .map(Cow::Owned)
+                        .map_err(|_| self.error(ErrorKind::InvalidUtf8));
+                }
+                Some(b'\\') => {
+                    let buffer = buffer.get_or_insert_with(Vec::new);
+                    buffer.extend_from_slice(run.as_bytes());
+                    self.parse_escape(start, quote, buffer)?;
+                }
+                Some(&byte) => {
+                    return Err(self.error(ErrorKind::ControlCharacterInString(char::from(byte))));
+                }
+                None => return Err(self.error_at(ErrorKind::UnterminatedString, start)),
+            }
+        }
+    }
+
+    fn parse_number(&mut self, allow_infinity: bool) -> Result<Number, ParseError> {
+        let mut text = std::mem::take(&mut self.scratch);
+        let number = number::parse_number(self, &mut text, allow_infinity);
+        self.scratch = text;
+
+        number
+    }
+}
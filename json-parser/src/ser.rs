@@ -0,0 +1,847 @@
+use crate::value::{Number, Value};
+use crate::writer::{self, Format, IoWriter, Serializer};
+use serde::ser::{
+    self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct,
+    SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write};
+use std::io;
+
+/// Error returned when a value can't be serialized as JSON.
+#[derive(Debug)]
+pub enum SerializeError {
+    /// Writing the output failed.
+    Io(io::Error),
+    /// A map key that isn't a string, a character, an integer or a unit variant, none of which
+    /// JSON can represent as an object key.
+    KeyNotString,
+    /// The `Serialize` implementation of the value failed.
+    Custom(String),
+}
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SerializeError::Io(error) => write!(f, "I/O error ({error})"),
+            SerializeError::KeyNotString => write!(f, "object keys must be strings"),
+            SerializeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for SerializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SerializeError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl ser::Error for SerializeError {
+    fn custom<T: Display>(message: T) -> Self {
+        SerializeError::Custom(message.to_string())
+    }
+}
+
+/// Only returned by the underlying writer, whose error is recovered by [`to_writer_with`].
+impl From<fmt::Error> for SerializeError {
+    fn from(_: fmt::Error) -> Self {
+        SerializeError::Io(io::Error::other("failed to format JSON"))
+    }
+}
+
+/// Serialize `value` as compact JSON.
+///
+/// The output is the same as the writer's for a [`Value`]: floats are written with the fewest
+/// digits that parse back to the same value, and infinities and NaN are written as `null`. Maps
+/// become objects, with integer keys written as strings, and enum variants with content become
+/// objects with a single member named after the variant.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Point {
+///     x0: f64,
+///     y0: f64,
+/// }
+///
+/// let json = json_parser::ser::to_string(&[Point { x0: 1.5, y0: -2.0 }]).unwrap();
+///
+/// assert_eq!(json, r#"[{"x0":1.5,"y0":-2.0}]"#);
+/// ```
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String, SerializeError> {
+    to_string_with(value, Format::Compact)
+}
+
+/// Serialize `value` as JSON with one element or member per line, indented by `indent` spaces
+/// per level.
+pub fn to_string_pretty<T: ?Sized + Serialize>(
+    value: &T,
+    indent: usize,
+) -> Result<String, SerializeError> {
+    to_string_with(value, Format::Pretty { indent })
+}
+
+/// Serialize `value` as JSON laid out according to `format`.
+pub fn to_string_with<T: ?Sized + Serialize>(
+    value: &T,
+    format: Format,
+) -> Result<String, SerializeError> {
+    let mut output = String::new();
+    value.serialize(&mut Serializer::new(&mut output, format))?;
+
+    Ok(output)
+}
+
+/// Serialize `value` to `writer` as compact JSON, like [`to_string`].
+///
+/// The writer isn't buffered, so wrap it in a [`std::io::BufWriter`] if it's a file.
+pub fn to_writer<W: io::Write, T: ?Sized + Serialize>(
+    writer: W,
+    value: &T,
+) -> Result<(), SerializeError> {
+    to_writer_with(writer, value, Format::Compact)
+}
+
+/// Serialize `value` to `writer` as JSON indented by `indent` spaces per level, like
+/// [`to_string_pretty`].
+pub fn to_writer_pretty<W: io::Write, T: ?Sized + Serialize>(
+    writer: W,
+    value: &T,
+    indent: usize,
+) -> Result<(), SerializeError> {
+    to_writer_with(writer, value, Format::Pretty { indent })
+}
+
+/// Serialize `value` to `writer` as JSON laid out according to `format`.
+pub fn to_writer_with<W: io::Write, T: ?Sized + Serialize>(
+    writer: W,
+    value: &T,
+    format: Format,
+) -> Result<(), SerializeError> {
+    let mut writer = IoWriter::new(writer);
+
+    value
+        .serialize(&mut Serializer::new(&mut writer, format))
+        .map_err(|error| match error {
+            SerializeError::Io(_) => SerializeError::Io(writer.take_error()),
+            error => error,
+        })
+}
+
+/// Serializes a [`Number::Raw`] as a 128-bit integer if it is one, and as the nearest `f64`
+/// otherwise.
+impl Serialize for Number {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Number::I64(value) => serializer.serialize_i64(*value),
+            Number::U64(value) => serializer.serialize_u64(*value),
+            Number::F64(value) => serializer.serialize_f64(*value),
+            Number::Raw(text) => {
+                if let Ok(value) = text.parse::<i128>() {
+                    serializer.serialize_i128(value)
+                } else if let Ok(value) = text.parse::<u128>() {
+                    serializer.serialize_u128(value)
+                } else {
+                    serializer.serialize_f64(self.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::String(string) => serializer.serialize_str(string),
+            Value::Number(number) => number.serialize(serializer),
+            Value::Boolean(boolean) => serializer.serialize_bool(*boolean),
+            Value::Array(array) => serializer.collect_seq(array),
+            Value::Object(object) => serializer.collect_map(object),
+            Value::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl<'s, 'w, W: Write> ser::Serializer for &'s mut Serializer<'w, W> {
+    type Ok = ();
+    type Error = SerializeError;
+    type SerializeSeq = Compound<'s, 'w, W>;
+    type SerializeTuple = Compound<'s, 'w, W>;
+    type SerializeTupleStruct = Compound<'s, 'w, W>;
+    type SerializeTupleVariant = Compound<'s, 'w, W>;
+    type SerializeMap = Compound<'s, 'w, W>;
+    type SerializeStruct = Compound<'s, 'w, W>;
+    type SerializeStructVariant = Compound<'s, 'w, W>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), SerializeError> {
+        Ok(self
+            .writer()
+            .write_str(if value { "true" } else { "false" })?)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), SerializeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), SerializeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), SerializeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), SerializeError> {
+        Ok(write!(self.writer(), "{value}")?)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), SerializeError> {
+        Ok(write!(self.writer(), "{value}")?)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), SerializeError> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), SerializeError> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), SerializeError> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), SerializeError> {
+        Ok(write!(self.writer(), "{value}")?)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), SerializeError> {
+        Ok(write!(self.writer(), "{value}")?)
+    }
+
+    /// Written with the fewest digits that parse back to the same `f32`, rather than those of the
+    /// `f64` it converts to.
+    fn serialize_f32(self, value: f32) -> Result<(), SerializeError> {
+        if value.is_finite() {
+            Ok(write!(self.writer(), "{value:?}")?)
+        } else {
+            self.serialize_unit()
+        }
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), SerializeError> {
+        Ok(writer::write_number(self.writer(), &Number::F64(value))?)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), SerializeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), SerializeError> {
+        Ok(writer::write_string(self.writer(), value)?)
+    }
+
+    /// Bytes are written as an array of numbers.
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), SerializeError> {
+        let mut array = self.serialize_seq(Some(value.len()))?;
+
+        for byte in value {
+            SerializeSeq::serialize_element(&mut array, byte)?;
+        }
+
+        SerializeSeq::end(array)
+    }
+
+    fn serialize_none(self) -> Result<(), SerializeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerializeError> {
+        Ok(self.writer().write_str("null")?)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerializeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerializeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        begin_variant(self, variant)?;
+        value.serialize(&mut *self)?;
+
+        Ok(self.end('}', 1)?)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        Compound::begin(self, '[', false)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        begin_variant(self, variant)?;
+        Compound::begin(self, '[', true)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        Compound::begin(self, '{', false)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'s, 'w, W>, SerializeError> {
+        begin_variant(self, variant)?;
+        Compound::begin(self, '{', true)
+    }
+}
+
+/// Open the object holding the content of an enum variant, up to the colon after its name.
+fn begin_variant<W: Write>(
+    serializer: &mut Serializer<'_, W>,
+    variant: &str,
+) -> Result<(), SerializeError> {
+    serializer.begin('{')?;
+    serializer.element(0)?;
+    writer::write_string(serializer.writer(), variant)?;
+
+    Ok(serializer.colon()?)
+}
+
+/// The array or object being written for a sequence, tuple, map or struct.
+pub(crate) struct Compound<'s, 'w, W> {
+    serializer: &'s mut Serializer<'w, W>,
+    /// Number of elements or members written so far.
+    length: usize,
+    /// Whether this is the content of an enum variant, in an object opened by [`begin_variant`].
+    variant: bool,
+}
+
+impl<'s, 'w, W: Write> Compound<'s, 'w, W> {
+    fn begin(
+        serializer: &'s mut Serializer<'w, W>,
+        bracket: char,
+        variant: bool,
+    ) -> Result<Self, SerializeError> {
+        serializer.begin(bracket)?;
+
+        Ok(Self {
+            serializer,
+            length: 0,
+            variant,
+        })
+    }
+
+    /// Start the next element or member.
+    fn element(&mut self) -> Result<(), SerializeError> {
+        self.serializer.element(self.length)?;
+        self.length += 1;
+
+        Ok(())
+    }
+
+    fn end(self, bracket: char) -> Result<(), SerializeError> {
+        self.serializer.end(bracket, self.length)?;
+
+        if self.variant {
+            self.serializer.end('}', 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> SerializeSeq for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.element()?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, ']')
+    }
+}
+
+impl<W: Write> SerializeTuple for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, ']')
+    }
+}
+
+impl<W: Write> SerializeTupleStruct for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, ']')
+    }
+}
+
+impl<W: Write> SerializeTupleVariant for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, ']')
+    }
+}
+
+impl<W: Write> SerializeMap for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), SerializeError> {
+        self.element()?;
+        key.serialize(KeySerializer {
+            serializer: &mut *self.serializer,
+        })
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.serializer.colon()?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, '}')
+    }
+}
+
+impl<W: Write> SerializeStruct for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.element()?;
+        writer::write_string(self.serializer.writer(), key)?;
+        SerializeMap::serialize_value(self, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, '}')
+    }
+}
+
+impl<W: Write> SerializeStructVariant for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Compound::end(self, '}')
+    }
+}
+
+/// Writes map keys, which have to be strings in JSON.
+struct KeySerializer<'s, 'w, W> {
+    serializer: &'s mut Serializer<'w, W>,
+}
+
+impl<W: Write> KeySerializer<'_, '_, W> {
+    /// Write an integer key as a string.
+    fn integer(self, value: impl Display) -> Result<(), SerializeError> {
+        Ok(write!(self.serializer.writer(), "\"{value}\"")?)
+    }
+}
+
+impl<W: Write> ser::Serializer for KeySerializer<'_, '_, W> {
+    type Ok = ();
+    type Error = SerializeError;
+    type SerializeSeq = Impossible<(), SerializeError>;
+    type SerializeTuple = Impossible<(), SerializeError>;
+    type SerializeTupleStruct = Impossible<(), SerializeError>;
+    type SerializeTupleVariant = Impossible<(), SerializeError>;
+    type SerializeMap = Impossible<(), SerializeError>;
+    type SerializeStruct = Impossible<(), SerializeError>;
+    type SerializeStructVariant = Impossible<(), SerializeError>;
+
+    fn serialize_str(self, value: &str) -> Result<(), SerializeError> {
+        Ok(writer::write_string(self.serializer.writer(), value)?)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), SerializeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerializeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), SerializeError> {
+        self.integer(value)
+    }
+
+    fn serialize_bool(self, _value: bool) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_none(self) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerializeError> {
+        Err(SerializeError::KeyNotString)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::JsonParser;
+    use crate::value::test::arbitrary_value;
+    use proptest::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x0: f64,
+        y0: f32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Segment(Point, Point),
+        Rectangle { width: u32, height: u32 },
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_values_round_trip(value in arbitrary_value()) {
+            let output = to_string(&value).unwrap();
+            prop_assert_eq!(&output, &value.to_string());
+
+            prop_assert_eq!(JsonParser::parse_from_bytes(output.as_bytes()), Ok(value.clone()));
+            prop_assert_eq!(crate::de::from_str::<Value>(&output), Ok(value));
+        }
+    }
+
+    #[test]
+    fn types_are_serialized() {
+        let point = |x0, y0| Point {
+            x0,
+            y0,
+            label: None,
+        };
+        let shapes = [
+            Shape::Empty,
+            Shape::Circle(2.5),
+            Shape::Segment(point(0.0, 0.1), point(-1.0, f32::NAN)),
+            Shape::Rectangle {
+                width: 3,
+                height: 2,
+            },
+        ];
+
+        assert_eq!(
+            to_string(&shapes).unwrap(),
+            r#"["Empty",{"Circle":2.5},{"Segment":[{"x0":0.0,"y0":0.1},{"x0":-1.0,"y0":null}]},{"Rectangle":{"width":3,"height":2}}]"#
+        );
+        assert_eq!(
+            to_string_pretty(&shapes[2..], 2).unwrap(),
+            "[\n  {\n    \"Segment\": [\n      {\n        \"x0\": 0.0,\n        \"y0\": 0.1\n      },\n      {\n        \"x0\": -1.0,\n        \"y0\": null\n      }\n    ]\n  },\n  {\n    \"Rectangle\": {\n      \"width\": 3,\n      \"height\": 2\n    }\n  }\n]"
+        );
+
+        let map = BTreeMap::from([(1, vec![Some("a\n")]), (-2, vec![None])]);
+        assert_eq!(to_string(&map).unwrap(), r#"{"-2":[null],"1":["a\n"]}"#);
+
+        assert_eq!(
+            to_string(&('é', (), u128::MAX, &b"\x00\xff"[..])).unwrap(),
+            r#"["é",null,340282366920938463463374607431768211455,[0,255]]"#
+        );
+        assert_eq!(to_string_pretty(&Vec::<u8>::new(), 2).unwrap(), "[]");
+    }
+
+    #[test]
+    fn types_round_trip() {
+        let shapes = vec![
+            Shape::Segment(
+                Point {
+                    x0: 95.262_354_347_647_15,
+                    y0: -33.782_22,
+                    label: Some("\"quoted\" 𝄞".to_string()),
+                },
+                Point {
+                    x0: f64::MIN_POSITIVE,
+                    y0: f32::MAX,
+                    label: None,
+                },
+            ),
+            Shape::Circle(1e300),
+            Shape::Empty,
+        ];
+
+        for output in [to_string(&shapes), to_string_pretty(&shapes, 4)] {
+            let round_tripped: Vec<Shape> = crate::de::from_str(&output.unwrap()).unwrap();
+            assert_eq!(round_tripped, shapes);
+        }
+    }
+
+    #[test]
+    fn values_match_the_writer() {
+        let inputs = [
+            r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377}],"a":{"b":[]}}"#,
+            r#"[true, false, null, "é𝄞\n\u0000", {}, [[]], -0.0, 1e300, 18446744073709551615]"#,
+            r#"{"a": 1, "b": {"c": [1, 2.5]}, "a": 3}"#,
+        ];
+
+        for input in inputs {
+            let value = JsonParser::parse_from_bytes(input.as_bytes()).unwrap();
+
+            assert_eq!(to_string(&value).unwrap(), value.to_string());
+            assert_eq!(to_string_pretty(&value, 2).unwrap(), format!("{value:#}"));
+
+            let mut output = Vec::new();
+            to_writer(&mut output, &value).unwrap();
+            assert_eq!(output, value.to_string().as_bytes());
+        }
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        struct Failing;
+
+        impl io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let map = BTreeMap::from([((1, 2), "pair")]);
+        assert!(matches!(to_string(&map), Err(SerializeError::KeyNotString)));
+
+        match to_writer(Failing, &[1, 2]) {
+            Err(SerializeError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::BrokenPipe),
+            result => panic!("Expected an I/O error, got {result:?}"),
+        }
+    }
+}
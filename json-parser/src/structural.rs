@@ -0,0 +1,721 @@
+use crate::error::{ErrorKind, ParseError};
+use crate::input::Input;
+use crate::scanner::Scanner;
+use crate::value::Number;
+use std::borrow::Cow;
+
+/// Finds the structural characters of a JSON document, 64 bytes at a time.
+///
+/// Those are `{`, `}`, `[`, `]`, `:` and `,` outside of strings, every unescaped quote, and the
+/// first byte of every run of other characters outside of strings, i.e. the start of each number
+/// or literal. Whatever is between two consecutive structurals is either whitespace or the rest of
+/// a string, number or literal.
+///
+/// Blocks are classified with SSE2 or AVX2 on x86_64 and NEON on aarch64, and bit by bit on other
+/// targets. Turning those classes into structurals follows simdjson's first stage.
+struct Indexer<'a> {
+    input: &'a [u8],
+    classifier: Classifier,
+    state: BlockState,
+    /// Number of bytes of `input` indexed so far.
+    indexed: usize,
+}
+
+impl<'a> Indexer<'a> {
+    /// Create an indexer for `input`, or return `None` if it's too large for 32-bit offsets.
+    fn new(input: &'a [u8], classifier: Classifier) -> Option<Self> {
+        u32::try_from(input.len()).ok()?;
+
+        Some(Self {
+            input,
+            classifier,
+            state: BlockState::default(),
+            indexed: 0,
+        })
+    }
+
+    /// Index the next `blocks` blocks of 64 bytes, appending their structurals, or return `false`
+    /// if everything was already indexed.
+    fn index(&mut self, blocks: usize, structurals: &mut Vec<u32>) -> bool {
+        let rest = &self.input[self.indexed..];
+
+        if rest.is_empty() {
+            return false;
+        }
+
+        let mut chunks = rest.chunks_exact(64).take(blocks);
+
+        for chunk in &mut chunks {
+            let block = self.classifier.classify(chunk.try_into().unwrap());
+            self.push(structurals, &block);
+        }
+
+        let rest = &self.input[self.indexed..];
+
+        if rest.len() < 64 && !rest.is_empty() {
+            // Padding with whitespace doesn't add any structurals.
+            let mut last = [b' '; 64];
+            last[..rest.len()].copy_from_slice(rest);
+
+            let block = self.classifier.classify(&last);
+            self.push(structurals, &block);
+        }
+
+        true
+    }
+
+    fn push(&mut self, structurals: &mut Vec<u32>, block: &Block) {
+        let mut bits = self.state.structurals(block);
+        // The input length was checked to fit when creating the indexer.
+        #[allow(clippy::cast_possible_truncation)]
+        let base = self.indexed as u32;
+
+        while bits != 0 {
+            structurals.push(base + bits.trailing_zeros());
+            bits &= bits - 1;
+        }
+
+        self.indexed = self.input.len().min(self.indexed + 64);
+    }
+}
+
+/// The instruction set used to classify blocks, detected at runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Classifier {
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+    #[cfg(any(test, not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+    Scalar,
+}
+
+impl Classifier {
+    #[cfg(target_arch = "x86_64")]
+    fn detect() -> Self {
+        if is_x86_feature_detected!("avx2") {
+            Classifier::Avx2
+        } else {
+            Classifier::Sse2
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn detect() -> Self {
+        Classifier::Neon
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn detect() -> Self {
+        Classifier::Scalar
+    }
+
+    fn classify(self, block: &[u8; 64]) -> Block {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Classifier::Sse2 => x86_64::classify_sse2(block),
+            // SAFETY: Only detected when the CPU supports AVX2.
+            #[cfg(target_arch = "x86_64")]
+            Classifier::Avx2 => unsafe { x86_64::classify_avx2(block) },
+            #[cfg(target_arch = "aarch64")]
+            Classifier::Neon => aarch64::classify_neon(block),
+            #[cfg(any(test, not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+            Classifier::Scalar => classify_scalar(block),
+        }
+    }
+}
+
+#[cfg(any(test, not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn is_operator(byte: u8) -> bool {
+    matches!(byte, b'{' | b'}' | b'[' | b']' | b':' | b',')
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// Character classes of the bytes in a 64-byte block, one bit per byte.
+#[derive(Debug, Default, PartialEq)]
+struct Block {
+    quote: u64,
+    backslash: u64,
+    whitespace: u64,
+    operator: u64,
+}
+
+/// What carries over from one block to the next.
+#[derive(Default)]
+struct BlockState {
+    /// 1 if the previous block ended with an odd-length run of backslashes.
+    odd_backslash: u64,
+    /// All ones if the previous block ended inside a string.
+    in_string: u64,
+    /// 1 if the last byte of the previous block was part of a number or literal.
+    other: u64,
+}
+
+impl BlockState {
+    /// Compute the structural bits of `block`.
+    fn structurals(&mut self, block: &Block) -> u64 {
+        let quote = block.quote & !self.escaped(block.backslash);
+
+        // Bits from each opening quote up to, but not including, its closing quote.
+        let in_string = prefix_xor(quote) ^ self.in_string;
+        self.in_string = 0_u64.wrapping_sub(in_string >> 63);
+
+        let other = !(block.operator | block.whitespace | quote | in_string);
+        let other_starts = other & !((other << 1) | self.other);
+        self.other = other >> 63;
+
+        (block.operator & !in_string) | quote | other_starts
+    }
+
+    /// Bits of the bytes preceded by an odd-length run of backslashes, using the carry trick from
+    /// simdjson.
+    fn escaped(&mut self, backslash: u64) -> u64 {
+        const EVEN_BITS: u64 = 0x5555_5555_5555_5555;
+        const ODD_BITS: u64 = !EVEN_BITS;
+
+        let start_edges = backslash & !(backslash << 1);
+        // A run continuing from the previous block starts at an odd offset if it had odd length.
+        let even_start_mask = EVEN_BITS ^ self.odd_backslash;
+        let even_starts = start_edges & even_start_mask;
+        let odd_starts = start_edges & !even_start_mask;
+
+        let even_carries = backslash.wrapping_add(even_starts);
+        let (mut odd_carries, ends_odd) = backslash.overflowing_add(odd_starts);
+        odd_carries |= self.odd_backslash;
+        self.odd_backslash = u64::from(ends_odd);
+
+        let even_carry_ends = even_carries & !backslash;
+        let odd_carry_ends = odd_carries & !backslash;
+
+        (even_carry_ends & ODD_BITS) | (odd_carry_ends & EVEN_BITS)
+    }
+}
+
+/// Turn every bit into the XOR of itself and all lower bits, so that bits between pairs of set
+/// bits get set.
+fn prefix_xor(mut bits: u64) -> u64 {
+    for shift in [1, 2, 4, 8, 16, 32] {
+        bits ^= bits << shift;
+    }
+
+    bits
+}
+
+#[cfg(any(test, not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn classify_scalar(block: &[u8; 64]) -> Block {
+    let mut classes = Block::default();
+
+    for (index, byte) in block.iter().enumerate() {
+        let bit = 1 << index;
+
+        match byte {
+            b'"' => classes.quote |= bit,
+            b'\\' => classes.backslash |= bit,
+            byte if is_whitespace(*byte) => classes.whitespace |= bit,
+            byte if is_operator(*byte) => classes.operator |= bit,
+            _ => {}
+        }
+    }
+
+    classes
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use super::Block;
+    use core::arch::x86_64::*;
+
+    pub(super) fn classify_sse2(block: &[u8; 64]) -> Block {
+        let mut classes = Block::default();
+
+        for (index, chunk) in block.chunks_exact(16).enumerate() {
+            // SAFETY: SSE2 is part of the x86_64 baseline, and the chunk is 16 bytes long.
+            let masks = unsafe {
+                let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+                let equals = |byte: u8| _mm_cmpeq_epi8(bytes, _mm_set1_epi8(byte.cast_signed()));
+
+                [
+                    _mm_movemask_epi8(equals(b'"')),
+                    _mm_movemask_epi8(equals(b'\\')),
+                    _mm_movemask_epi8(_mm_or_si128(
+                        _mm_or_si128(equals(b' '), equals(b'\t')),
+                        _mm_or_si128(equals(b'\n'), equals(b'\r')),
+                    )),
+                    _mm_movemask_epi8(_mm_or_si128(
+                        _mm_or_si128(
+                            _mm_or_si128(equals(b'{'), equals(b'}')),
+                            _mm_or_si128(equals(b'['), equals(b']')),
+                        ),
+                        _mm_or_si128(equals(b':'), equals(b',')),
+                    )),
+                ]
+            };
+
+            let shift = index * 16;
+            classes.quote |= u64::from(masks[0].cast_unsigned()) << shift;
+            classes.backslash |= u64::from(masks[1].cast_unsigned()) << shift;
+            classes.whitespace |= u64::from(masks[2].cast_unsigned()) << shift;
+            classes.operator |= u64::from(masks[3].cast_unsigned()) << shift;
+        }
+
+        classes
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn classify_avx2(block: &[u8; 64]) -> Block {
+        let mut classes = Block::default();
+
+        for (index, chunk) in block.chunks_exact(32).enumerate() {
+            let bytes = _mm256_loadu_si256(chunk.as_ptr().cast());
+            let equals = |byte: u8| _mm256_cmpeq_epi8(bytes, _mm256_set1_epi8(byte.cast_signed()));
+
+            let quote = _mm256_movemask_epi8(equals(b'"'));
+            let backslash = _mm256_movemask_epi8(equals(b'\\'));
+            let whitespace = _mm256_movemask_epi8(_mm256_or_si256(
+                _mm256_or_si256(equals(b' '), equals(b'\t')),
+                _mm256_or_si256(equals(b'\n'), equals(b'\r')),
+            ));
+            let operator = _mm256_movemask_epi8(_mm256_or_si256(
+                _mm256_or_si256(
+                    _mm256_or_si256(equals(b'{'), equals(b'}')),
+                    _mm256_or_si256(equals(b'['), equals(b']')),
+                ),
+                _mm256_or_si256(equals(b':'), equals(b',')),
+            ));
+
+            let shift = index * 32;
+            classes.quote |= u64::from(quote.cast_unsigned()) << shift;
+            classes.backslash |= u64::from(backslash.cast_unsigned()) << shift;
+            classes.whitespace |= u64::from(whitespace.cast_unsigned()) << shift;
+            classes.operator |= u64::from(operator.cast_unsigned()) << shift;
+        }
+
+        classes
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use super::Block;
+    use core::arch::aarch64::*;
+
+    pub(super) fn classify_neon(block: &[u8; 64]) -> Block {
+        let mut classes = Block::default();
+
+        for (index, chunk) in block.chunks_exact(16).enumerate() {
+            // SAFETY: NEON is part of the aarch64 baseline, and the chunk is 16 bytes long.
+            let masks = unsafe {
+                let bytes = vld1q_u8(chunk.as_ptr());
+                let equals = |byte: u8| vceqq_u8(bytes, vdupq_n_u8(byte));
+
+                [
+                    movemask(equals(b'"')),
+                    movemask(equals(b'\\')),
+                    movemask(vorrq_u8(
+                        vorrq_u8(equals(b' '), equals(b'\t')),
+                        vorrq_u8(equals(b'\n'), equals(b'\r')),
+                    )),
+                    movemask(vorrq_u8(
+                        vorrq_u8(
+                            vorrq_u8(equals(b'{'), equals(b'}')),
+                            vorrq_u8(equals(b'['), equals(b']')),
+                        ),
+                        vorrq_u8(equals(b':'), equals(b',')),
+                    )),
+                ]
+            };
+
+            let shift = index * 16;
+            classes.quote |= u64::from(masks[0]) << shift;
+            classes.backslash |= u64::from(masks[1]) << shift;
+            classes.whitespace |= u64::from(masks[2]) << shift;
+            classes.operator |= u64::from(masks[3]) << shift;
+        }
+
+        classes
+    }
+
+    /// Collect the top bit of each byte of a comparison result into a 16-bit mask, as NEON has
+    /// no equivalent of `movemask`.
+    unsafe fn movemask(matches: uint8x16_t) -> u16 {
+        const WEIGHTS: [u8; 16] = [1, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128];
+
+        let bits = vandq_u8(matches, vld1q_u8(WEIGHTS.as_ptr()));
+        let low = vaddv_u8(vget_low_u8(bits));
+        let high = vaddv_u8(vget_high_u8(bits));
+
+        u16::from(low) | (u16::from(high) << 8)
+    }
+}
+
+/// Number of 64-byte blocks indexed at a time, so the index stays small and in cache.
+const WINDOW_BLOCKS: usize = 1024;
+
+/// A [`Scanner`] that uses the structural characters found by an [`Indexer`] to jump over
+/// whitespace and to the closing quote of strings, instead of looking at every byte.
+///
+/// The input is indexed lazily as the parser gets to it, one window of blocks at a time.
+pub(crate) struct IndexedScanner<'a> {
+    scanner: Scanner<'a>,
+    indexer: Indexer<'a>,
+    /// Structurals of the current window, from the last one the parser hasn't moved past.
+    structurals: Vec<u32>,
+    /// Index into `structurals` of the first entry at or after the scanner's offset.
+    next: usize,
+}
+
+impl<'a> IndexedScanner<'a> {
+    /// Create a scanner for `input`, or return `None` if it's too large for 32-bit offsets.
+    pub(crate) fn new(input: &'a [u8]) -> Option<Self> {
+        Self::with_classifier(input, Classifier::detect())
+    }
+
+    fn with_classifier(input: &'a [u8], classifier: Classifier) -> Option<Self> {
+        Some(Self {
+            scanner: Scanner::new(input),
+            indexer: Indexer::new(input, classifier)?,
+            structurals: Vec::with_capacity(WINDOW_BLOCKS * 64),
+            next: 0,
+        })
+    }
+
+    /// Offset of the structural `count` entries after `next`, indexing more input if needed.
+    fn structural(&mut self, count: usize) -> Option<usize> {
+        while self.next + count >= self.structurals.len() {
+            self.structurals.drain(..self.next);
+            self.next = 0;
+
+            if !self.indexer.index(WINDOW_BLOCKS, &mut self.structurals) {
+                return None;
+            }
+        }
+
+        Some(self.structurals[self.next + count] as usize)
+    }
+
+    /// Offset of the first structural at or after `offset`, moving `next` up to it.
+    fn next_structural(&mut self, offset: usize) -> Option<usize> {
+        while let Some(structural) = self.structural(0) {
+            if structural >= offset {
+                return Some(structural);
+            }
+
+            self.next += 1;
+        }
+
+        None
+    }
+}
+
+impl<'a> Input<'a> for IndexedScanner<'a> {
+    type Mark = usize;
+
+    fn peek(&mut self) -> Result<Option<u8>, ParseError> {
+        self.scanner.peek()
+    }
+
+    fn discard(&mut self) {
+        self.scanner.discard();
+    }
+
+    fn mark(&self) -> usize {
+        self.scanner.mark()
+    }
+
+    fn error_at(&self, kind: ErrorKind, offset: usize) -> ParseError {
+        self.scanner.error_at(kind, offset)
+    }
+
+    fn unexpected(&mut self) -> ParseError {
+        self.scanner.unexpected()
+    }
+
+    /// Whitespace is followed by a structural unless it ends the input, so jump straight to it.
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        let offset = self.scanner.mark();
+        let input = self.scanner.input();
+
+        if input.get(offset).is_some_and(|byte| is_whitespace(*byte)) {
+            let target = self.next_structural(offset).unwrap_or(input.len());
+            self.scanner.seek(target);
+        }
+
+        Ok(())
+    }
+
+    /// Borrow double-quoted strings without escapes or control characters straight up to the
+    /// closing quote, and leave anything else to the [`Scanner`].
+    fn parse_quoted_string(&mut self, quote: u8) -> Result<Cow<'a, str>, ParseError> {
+        let start = self.scanner.mark();
+        let input = self.scanner.input();
+
+        if quote == b'"' && self.next_structural(start) == Some(start) {
+            if let Some(end) = self.structural(1) {
+                let content = &input[start + 1..end];
+
+                if input[end] == b'"' && !content.iter().any(|byte| *byte == b'\\' || *byte < 0x20)
+                {
+                    let string = self.scanner.validate_utf8(start + 1, end)?;
+                    self.scanner.seek(end + 1);
+                    self.next += 2;
+
+                    return Ok(Cow::Borrowed(string));
+                }
+            }
+        }
+
+        self.scanner.parse_quoted_string(quote)
+    }
+
+    fn parse_number(&mut self, allow_infinity: bool) -> Result<Number, ParseError> {
+        self.scanner.parse_number(allow_infinity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::ParserOptions;
+    use crate::parser::JsonParser;
+
+    /// Inputs from the tokenizer tests, valid and invalid.
+    const TOKENIZER_INPUTS: &[&str] = &[
+        r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377,"x1":41.844453001935875,"y1":-78.10213222087448},{"x0":115.42029308864215,"y0":87.52060937339934,"x1":83.39640643072113,"y1":28.643090267505812},{"sample":"string sample","nullable":null}]}"#,
+        r#"{"key1":"ࠄࠀࠆࠄࠀࠁࠃ","key2":"value2"}"#,
+        "{\n  \"key\": ;\n}",
+        r#"{"key": "value}"#,
+        "[1, 2x]",
+        "[1, 1.2.3]",
+        "[\n  --1]",
+        r#"{"a": 1} {"b": 2}"#,
+        "1\n2",
+        r#"{"a": [1, 2}"#,
+        r#"{"a": [1, 2]"#,
+        "]",
+        "{ \"key\" :\t[ 1 , 2.5 ]\r\n}",
+        r#""a\"b""#,
+        r#""a\\b""#,
+        r#""\b\f\n\r\t""#,
+        r#""\\\"""#,
+        r#""Aé""#,
+        r#""𝄞""#,
+        r#"{"a\"b":"c\"d"}"#,
+        r#""ab\x""#,
+        r#""\u12G4""#,
+        r#""\"#,
+        r#""\uD834x""#,
+        "\"a\nb\"",
+        "\"\u{7f}ü\"",
+        "[true, false, null]",
+        "nope",
+        "[truX]",
+        "[nul\nl]",
+        "truex",
+        "[1]\0",
+        "[1,\0 2]",
+        "[1e+5, -7, 18446744073709551615, 123456789012345678901234567890]",
+    ];
+
+    /// Structurals found by looking at one byte at a time, to check the [`Indexer`] against.
+    fn scalar_index(input: &[u8]) -> Vec<u32> {
+        let mut structurals = Vec::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut previous_is_other = false;
+
+        for (offset, byte) in (0..).zip(input) {
+            // Backslashes escape the next byte anywhere, which only matters for quotes.
+            let is_quote = *byte == b'"' && !escaped;
+            escaped = *byte == b'\\' && !escaped;
+
+            if in_string {
+                if is_quote {
+                    in_string = false;
+                    structurals.push(offset);
+                }
+
+                previous_is_other = false;
+            } else if is_quote {
+                in_string = true;
+                structurals.push(offset);
+                previous_is_other = false;
+            } else if is_operator(*byte) {
+                structurals.push(offset);
+                previous_is_other = false;
+            } else if is_whitespace(*byte) {
+                previous_is_other = false;
+            } else {
+                if !previous_is_other {
+                    structurals.push(offset);
+                }
+
+                previous_is_other = true;
+            }
+        }
+
+        structurals
+    }
+
+    /// Index `input` with `classifier`, `blocks` blocks at a time.
+    fn index(input: &[u8], classifier: Classifier, blocks: usize) -> Vec<u32> {
+        let mut indexer = Indexer::new(input, classifier).unwrap();
+        let mut structurals = Vec::new();
+
+        while indexer.index(blocks, &mut structurals) {}
+
+        structurals
+    }
+
+    /// Deterministic pseudo-random inputs built mostly from JSON's own characters, so that
+    /// strings, escapes and values straddle block boundaries in many different ways.
+    fn fuzz_corpus() -> Vec<Vec<u8>> {
+        const ALPHABET: &[u8] = b"{}[]:,\"\\\" \t\n\r0123456789-+.eEtruefalsn\x00\x1fa\xc3\xbc";
+
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        (0..2000)
+            .map(|_| {
+                let length = usize::try_from(next() % 300).unwrap();
+
+                (0..length)
+                    .map(|_| ALPHABET[usize::try_from(next()).unwrap() % ALPHABET.len()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn corpus() -> Vec<Vec<u8>> {
+        let mut corpus: Vec<Vec<u8>> = TOKENIZER_INPUTS
+            .iter()
+            .map(|input| input.as_bytes().to_vec())
+            .collect();
+
+        // Long strings and runs of backslashes crossing block boundaries.
+        corpus.push(format!("[\"{}\", 1]", "a".repeat(130)).into_bytes());
+        corpus.push(
+            format!(
+                "[\"{}\\\\\", \"{}\\\"\"]",
+                "b".repeat(61),
+                "\\\\".repeat(40)
+            )
+            .into_bytes(),
+        );
+        corpus.push(format!("{}[1]", " ".repeat(200)).into_bytes());
+        corpus.extend(fuzz_corpus());
+
+        corpus
+    }
+
+    #[test]
+    fn blocks_are_classified_like_scalar() {
+        for input in corpus() {
+            for chunk in input.chunks(64) {
+                let mut block = [b' '; 64];
+                block[..chunk.len()].copy_from_slice(chunk);
+
+                let expected = classify_scalar(&block);
+
+                #[cfg(target_arch = "x86_64")]
+                {
+                    assert_eq!(x86_64::classify_sse2(&block), expected);
+
+                    if is_x86_feature_detected!("avx2") {
+                        assert_eq!(unsafe { x86_64::classify_avx2(&block) }, expected);
+                    }
+                }
+
+                #[cfg(target_arch = "aarch64")]
+                assert_eq!(aarch64::classify_neon(&block), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn index_matches_scalar() {
+        for input in corpus() {
+            let expected = scalar_index(&input);
+
+            for blocks in [1, WINDOW_BLOCKS] {
+                assert_eq!(
+                    index(&input, Classifier::detect(), blocks),
+                    expected,
+                    "Indexing {:?}",
+                    String::from_utf8_lossy(&input)
+                );
+
+                assert_eq!(
+                    index(&input, Classifier::Scalar, blocks),
+                    expected,
+                    "Indexing {:?}",
+                    String::from_utf8_lossy(&input)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn index_positions() {
+        let input = br#"{"a\"b": [1, true], "c" :null}"#;
+
+        // {  "  "  :  [  1  ,  t  ]  ,  "  "  :  n  }
+        assert_eq!(
+            scalar_index(input),
+            vec![0, 1, 6, 7, 9, 10, 11, 13, 17, 18, 20, 22, 24, 25, 29]
+        );
+    }
+
+    fn parse<'a>(input: &mut impl Input<'a>) -> Result<crate::value::Value, ParseError> {
+        JsonParser::parse_document(input, &ParserOptions::default())
+    }
+
+    #[test]
+    fn parser_matches_scalar() {
+        for input in corpus() {
+            let mut scanner = Scanner::new(&input);
+            let expected = parse(&mut scanner);
+
+            let mut indexed = IndexedScanner::with_classifier(&input, Classifier::Scalar).unwrap();
+            let scalar = parse(&mut indexed);
+            assert_eq!(scalar, expected);
+
+            let mut indexed = IndexedScanner::new(&input).unwrap();
+            let value = parse(&mut indexed);
+
+            assert_eq!(
+                value,
+                expected,
+                "Parsing {:?}",
+                String::from_utf8_lossy(&input)
+            );
+        }
+    }
+
+    #[test]
+    fn parser_crosses_windows() {
+        let pair = r#"{"x0": 95.26235434764715, "y0": -33.78221816487377, "key": "a\"b"}"#;
+        let input = format!("[{}]", vec![pair; 5000].join(",\n  "));
+        assert!(input.len() > WINDOW_BLOCKS * 64 * 2);
+
+        let expected = parse(&mut Scanner::new(input.as_bytes()));
+        let mut indexed = IndexedScanner::new(input.as_bytes()).unwrap();
+        let value = parse(&mut indexed);
+
+        assert_eq!(value, expected);
+        assert!(value.is_ok());
+    }
+}
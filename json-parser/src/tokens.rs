@@ -1,8 +1,8 @@
+use crate::error::{ErrorKind, ParseError, Position};
+use crate::input::Input;
 use crate::reader::JsonReader;
 use crate::value::Number;
-use std::fs::File;
-use std::io::{BufReader, Cursor, Read, Seek};
-use std::iter::Peekable;
+use std::io::{Cursor, Read};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -21,189 +21,138 @@ pub enum Token {
 
 pub struct JsonTokenizer<T>
 where
-    T: Read + Seek,
+    T: Read,
 {
     tokens: Vec<Token>,
-    iterator: Peekable<JsonReader<T>>,
+    reader: JsonReader<T>,
+    /// Currently open brackets (`{` or `[`), along with where they were opened.
+    open_brackets: Vec<(char, Position)>,
+    /// Whether a complete top-level value has already been tokenized.
+    root_complete: bool,
 }
 
 impl<T> JsonTokenizer<T>
 where
-    T: Read + Seek,
+    T: Read,
 {
     #[must_use]
-    pub fn new(reader: File) -> JsonTokenizer<File> {
-        let json_reader = JsonReader::<File>::new(BufReader::new(reader));
-
+    pub fn new(reader: T) -> Self {
         JsonTokenizer {
-            iterator: json_reader.peekable(),
+            reader: JsonReader::new(reader),
             tokens: vec![],
+            open_brackets: vec![],
+            root_complete: false,
         }
     }
 
     #[must_use]
     pub fn from_bytes<'a>(input: &'a [u8]) -> JsonTokenizer<Cursor<&'a [u8]>> {
-        let json_reader = JsonReader::<Cursor<&'a [u8]>>::from_bytes(input);
-
         JsonTokenizer {
-            iterator: json_reader.peekable(),
+            reader: JsonReader::<Cursor<&'a [u8]>>::from_bytes(input),
             tokens: Vec::with_capacity(input.len()),
+            open_brackets: vec![],
+            root_complete: false,
         }
     }
 
-    fn parse_string(&mut self) -> String {
-        let mut string_characters = Vec::<char>::new();
+    /// Consume `literal` and push `token`, failing at the first character that doesn't match.
+    fn parse_literal(&mut self, literal: &[u8], token: Token) -> Result<(), ParseError> {
+        self.begin_value()?;
+        self.reader.parse_literal(literal)?;
+        self.tokens.push(token);
+        self.end_value();
 
-        for character in self.iterator.by_ref() {
-            if character == '"' {
-                break;
-            }
+        Ok(())
+    }
 
-            string_characters.push(character);
+    /// Record the start of a new value, rejecting it if a top-level value was already complete.
+    fn begin_value(&self) -> Result<(), ParseError> {
+        if self.root_complete {
+            return Err(self.reader.error(ErrorKind::TrailingCharacters));
         }
 
-        String::from_iter(string_characters)
+        Ok(())
     }
 
-    fn parse_number(&mut self) -> Result<Number, ()> {
-        let mut number_characters = Vec::<char>::new();
-        let mut is_decimal = false;
-        let mut epsilon_characters = Vec::<char>::new();
-        let mut is_epsilon_characters = false;
-
-        while let Some(character) = self.iterator.peek() {
-            match character {
-                '-' => {
-                    if is_epsilon_characters {
-                        epsilon_characters.push('-');
-                    } else {
-                        number_characters.push('-');
-                    }
-                    let _ = self.iterator.next();
-                }
-                digit @ '0'..='9' => {
-                    if is_epsilon_characters {
-                        epsilon_characters.push(*digit);
-                    } else {
-                        number_characters.push(*digit);
-                    }
-                    let _ = self.iterator.next();
-                }
-                '.' => {
-                    number_characters.push('.');
-                    is_decimal = true;
-                    let _ = self.iterator.next();
-                }
-                '}' | ',' | ']' | ':' => {
-                    break;
-                }
-                'e' | 'E' => {
-                    is_epsilon_characters = true;
-                    let _ = self.iterator.next();
-                }
-                _ => {
-                    panic!("Unexpected character while parsing number: {character}")
-                }
-            }
+    /// Record the end of a value, marking the top-level value as complete if it isn't nested.
+    fn end_value(&mut self) {
+        if self.open_brackets.is_empty() {
+            self.root_complete = true;
         }
+    }
+
+    fn open_bracket(&mut self, bracket: char, token: Token) -> Result<(), ParseError> {
+        self.begin_value()?;
+        self.open_brackets.push((bracket, self.reader.position()));
+        self.tokens.push(token);
+        self.reader.discard();
+
+        Ok(())
+    }
 
-        if is_decimal {
-            Ok(Number::F64(
-                String::from_iter(number_characters)
-                    .parse::<f64>()
-                    .map_err(|_| ())?,
-            ))
-        } else {
-            Ok(Number::I64(
-                String::from_iter(number_characters)
-                    .parse::<i64>()
-                    .map_err(|_| ())?,
-            ))
+    fn close_bracket(&mut self, bracket: char, token: Token) -> Result<(), ParseError> {
+        match self.open_brackets.pop() {
+            Some((open, _)) if open == bracket => {}
+            _ => return Err(self.reader.error(ErrorKind::UnbalancedBrackets)),
         }
+
+        self.tokens.push(token);
+        self.reader.discard();
+        self.end_value();
+
+        Ok(())
     }
 
     #[cfg_attr(feature = "profile", instrument)]
-    pub fn tokenize_json(&mut self) -> Result<&[Token], ()> {
-        while let Some(character) = self.iterator.peek() {
-            match *character {
-                '"' => {
+    pub fn tokenize_json(&mut self) -> Result<&[Token], ParseError> {
+        while let Some(byte) = self.reader.peek()? {
+            match byte {
+                b'"' => {
+                    self.begin_value()?;
                     self.tokens.push(Token::Quotes);
 
-                    // Skip quote token since we already added it to the tokens list.
-                    let _ = self.iterator.next();
-
-                    let string = self.parse_string();
+                    let string = self.reader.parse_string()?.into_owned();
 
                     self.tokens.push(Token::String(string));
                     self.tokens.push(Token::Quotes);
+                    self.end_value();
                 }
-                '{' => {
-                    self.tokens.push(Token::CurlyOpen);
-                    let _ = self.iterator.next();
-                }
-                '}' => {
-                    self.tokens.push(Token::CurlyClose);
-                    let _ = self.iterator.next();
-                }
-                '[' => {
-                    self.tokens.push(Token::ArrayOpen);
-                    let _ = self.iterator.next();
-                }
-                ']' => {
-                    self.tokens.push(Token::ArrayClose);
-                    let _ = self.iterator.next();
-                }
-                ',' => {
+                b'{' => self.open_bracket('{', Token::CurlyOpen)?,
+                b'}' => self.close_bracket('{', Token::CurlyClose)?,
+                b'[' => self.open_bracket('[', Token::ArrayOpen)?,
+                b']' => self.close_bracket('[', Token::ArrayClose)?,
+                b',' => {
                     self.tokens.push(Token::Comma);
-                    let _ = self.iterator.next();
+                    self.reader.discard();
                 }
-                ':' => {
+                b':' => {
                     self.tokens.push(Token::Colon);
-                    let _ = self.iterator.next();
+                    self.reader.discard();
                 }
-                '-' | '0'..='9' => {
-                    let number = self.parse_number()?;
+                b'-' | b'0'..=b'9' => {
+                    self.begin_value()?;
+                    let number = self.reader.parse_number(false)?;
                     self.tokens.push(Token::Number(number));
+                    self.end_value();
                 }
-                '\0' => break,
-                'n' => {
-                    self.tokens.push(Token::Null);
-
-                    // Advance iterator by 4 for null character
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                }
-                't' => {
-                    self.tokens.push(Token::Boolean(true));
-
-                    // Advance iterator by 4 for true keyword
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                }
-                'f' => {
-                    self.tokens.push(Token::Boolean(false));
-
-                    // Advance iterator by 5 for false character
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                    let _ = self.iterator.next();
-                }
-                character => {
-                    if character.is_ascii_whitespace() {
-                        continue;
+                b'n' => self.parse_literal(b"null", Token::Null)?,
+                b't' => self.parse_literal(b"true", Token::Boolean(true))?,
+                b'f' => self.parse_literal(b"false", Token::Boolean(false))?,
+                b' ' | b'\t' | b'\n' | b'\r' => self.reader.skip_whitespace()?,
+                _ => {
+                    if self.root_complete {
+                        return Err(self.reader.error(ErrorKind::TrailingCharacters));
                     }
 
-                    panic!("Unexpected character: ;{character};")
+                    return Err(self.reader.unexpected());
                 }
             }
         }
 
+        if let Some((_, position)) = self.open_brackets.last() {
+            return Err(ParseError::new(ErrorKind::UnbalancedBrackets, *position));
+        }
+
         Ok(&self.tokens)
     }
 }
@@ -211,10 +160,10 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::map::Map;
     use crate::parser::JsonParser;
     use crate::value::Number::F64;
-    use std::collections::HashMap;
-    use std::io::Cursor;
+    use std::io::BufReader;
 
     #[test]
     fn test_tokenizer() {
@@ -297,23 +246,23 @@ mod test {
         let input = r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377,"x1":41.844453001935875,"y1":-78.10213222087448},{"x0":115.42029308864215,"y0":87.52060937339934,"x1":83.39640643072113,"y1":28.643090267505812},{"sample":"string sample","nullable":null}]}"#;
         let json_parser = JsonParser::parse_from_bytes(input.as_bytes());
 
-        let mut entry1 = HashMap::new();
+        let mut entry1 = Map::new();
         entry1.insert("y0".to_string(), Number(F64(-33.782_218_164_873_77)));
         entry1.insert("x0".to_string(), Number(F64(95.262_354_347_647_15)));
         entry1.insert("y1".to_string(), Number(F64(-78.102_132_220_874_48)));
         entry1.insert("x1".to_string(), Number(F64(41.844_453_001_935_875)));
 
-        let mut entry2 = HashMap::new();
+        let mut entry2 = Map::new();
         entry2.insert("y0".to_string(), Number(F64(87.520_609_373_399_34)));
         entry2.insert("x0".to_string(), Number(F64(115.420_293_088_642_15)));
         entry2.insert("x1".to_string(), Number(F64(83.396_406_430_721_13)));
         entry2.insert("y1".to_string(), Number(F64(28.643_090_267_505_812)));
 
-        let mut entry3 = HashMap::new();
+        let mut entry3 = Map::new();
         entry3.insert("sample".to_string(), String("string sample".to_string()));
         entry3.insert("nullable".to_string(), Null);
 
-        let mut pairs = HashMap::new();
+        let mut pairs = Map::new();
         pairs.insert(
             "pairs".to_string(),
             Array(vec![Object(entry1), Object(entry2), Object(entry3)]),
@@ -329,10 +278,264 @@ mod test {
         let input = r#"{"key1":"ࠄࠀࠆࠄࠀࠁࠃ","key2":"value2"}"#;
         let json_parser = JsonParser::parse_from_bytes(input.as_bytes());
 
-        let mut pairs = HashMap::new();
+        let mut pairs = Map::new();
         pairs.insert("key1".to_string(), String("ࠄࠀࠆࠄࠀࠁࠃ".to_string()));
         pairs.insert("key2".to_string(), String("value2".to_string()));
 
         assert_eq!(json_parser.unwrap(), Object(pairs));
     }
+
+    fn tokenize_error(input: &str) -> ParseError {
+        let mut tokenizer = JsonTokenizer::<BufReader<Cursor<&[u8]>>>::from_bytes(input.as_bytes());
+
+        tokenizer.tokenize_json().unwrap_err()
+    }
+
+    #[test]
+    fn unexpected_character_error() {
+        let error = tokenize_error("{\n  \"key\": ;\n}");
+
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter(';'));
+        assert_eq!(error.offset(), 11);
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 10);
+    }
+
+    #[test]
+    fn unterminated_string_error() {
+        let error = tokenize_error(r#"{"key": "value}"#);
+
+        assert_eq!(error.kind(), &ErrorKind::UnterminatedString);
+        assert_eq!(error.offset(), 8);
+        assert_eq!(error.column(), 9);
+    }
+
+    #[test]
+    fn invalid_number_error() {
+        let error = tokenize_error("[1, 2x]");
+
+        assert_eq!(error.kind(), &ErrorKind::InvalidNumber);
+        assert_eq!(error.offset(), 5);
+
+        let error = tokenize_error("[1, 1.2.3]");
+
+        assert_eq!(error.kind(), &ErrorKind::InvalidNumber);
+        assert_eq!(error.offset(), 7);
+
+        let error = tokenize_error("[\n  --1]");
+
+        assert_eq!(error.kind(), &ErrorKind::InvalidNumber);
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 4);
+    }
+
+    #[test]
+    fn trailing_characters_error() {
+        let error = tokenize_error(r#"{"a": 1} {"b": 2}"#);
+
+        assert_eq!(error.kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(error.offset(), 9);
+
+        let error = tokenize_error("1\n2");
+
+        assert_eq!(error.kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 1);
+    }
+
+    #[test]
+    fn unbalanced_brackets_error() {
+        let error = tokenize_error(r#"{"a": [1, 2}"#);
+
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 11);
+
+        let error = tokenize_error(r#"{"a": [1, 2]"#);
+
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 0);
+
+        let error = tokenize_error("]");
+
+        assert_eq!(error.kind(), &ErrorKind::UnbalancedBrackets);
+        assert_eq!(error.offset(), 0);
+    }
+
+    #[test]
+    fn parser_reports_positioned_errors() {
+        let error = JsonParser::parse_from_bytes(br#"{"key": "value"#).unwrap_err();
+
+        assert_eq!(error.kind(), &ErrorKind::UnterminatedString);
+        assert_eq!(
+            error.to_string(),
+            "unterminated string at line 1, column 9 (byte 8)"
+        );
+    }
+
+    #[test]
+    fn whitespace_between_tokens() {
+        let input = "{ \"key\" :\t[ 1 , 2.5 ]\r\n}";
+
+        let mut tokenizer = JsonTokenizer::<BufReader<Cursor<&[u8]>>>::from_bytes(input.as_bytes());
+        let tokens = tokenizer.tokenize_json().unwrap();
+
+        assert_eq!(tokens.len(), 11);
+    }
+
+    fn tokenize_string(input: &str) -> Result<std::string::String, ParseError> {
+        let mut tokenizer = JsonTokenizer::<BufReader<Cursor<&[u8]>>>::from_bytes(input.as_bytes());
+
+        match tokenizer.tokenize_json()? {
+            [Token::Quotes, Token::String(string), Token::Quotes] => Ok(string.clone()),
+            tokens => panic!("Expected a single string, got {tokens:?}"),
+        }
+    }
+
+    #[test]
+    fn string_simple_escapes() {
+        assert_eq!(tokenize_string(r#""a\"b""#).unwrap(), "a\"b");
+        assert_eq!(tokenize_string(r#""a\\b""#).unwrap(), "a\\b");
+        assert_eq!(tokenize_string(r#""a\/b""#).unwrap(), "a/b");
+        assert_eq!(
+            tokenize_string(r#""\b\f\n\r\t""#).unwrap(),
+            "\u{8}\u{c}\n\r\t"
+        );
+        assert_eq!(tokenize_string(r#""\\\"""#).unwrap(), "\\\"");
+    }
+
+    #[test]
+    fn string_unicode_escapes() {
+        assert_eq!(tokenize_string(r#""\u0041\u00e9""#).unwrap(), "Aé");
+        assert_eq!(tokenize_string(r#""\u0000""#).unwrap(), "\u{0}");
+        assert_eq!(tokenize_string(r#""\u20AC\u20ac""#).unwrap(), "€€");
+        assert_eq!(tokenize_string(r#""\uD834\uDD1E""#).unwrap(), "𝄞");
+        assert_eq!(tokenize_string(r#""\ud83d\ude00!""#).unwrap(), "😀!");
+    }
+
+    #[test]
+    fn string_escaped_quote_in_object() {
+        use crate::value::Value::*;
+
+        let json = JsonParser::parse_from_bytes(br#"{"a\"b":"c\"d"}"#).unwrap();
+
+        let mut object = Map::new();
+        object.insert("a\"b".to_string(), String("c\"d".to_string()));
+
+        assert_eq!(json, Object(object));
+    }
+
+    #[test]
+    fn string_invalid_escapes() {
+        let error = tokenize_string(r#""ab\x""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidEscape);
+        assert_eq!(error.offset(), 3);
+
+        let error = tokenize_string(r#""\u12G4""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+        assert_eq!(error.offset(), 1);
+
+        let error = tokenize_string(r#""\u12""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+
+        let error = tokenize_string(r#""\"#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnterminatedString);
+        assert_eq!(error.offset(), 0);
+    }
+
+    #[test]
+    fn string_lone_surrogates() {
+        let error = tokenize_string(r#""\uD834""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+
+        let error = tokenize_string(r#""\uD834x""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+
+        let error = tokenize_string(r#""\uD834A""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+
+        let error = tokenize_string(r#""\uDD1E""#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidUnicodeEscape);
+    }
+
+    #[test]
+    fn string_unescaped_control_characters() {
+        let error = tokenize_string("\"a\nb\"").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ControlCharacterInString('\n'));
+        assert_eq!(error.offset(), 2);
+
+        let error = tokenize_string("\"\t\"").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ControlCharacterInString('\t'));
+
+        let error = tokenize_string("\"\u{1f}\"").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ControlCharacterInString('\u{1f}'));
+
+        // DEL and non-ASCII characters don't need escaping.
+        assert_eq!(tokenize_string("\"\u{7f}ü\"").unwrap(), "\u{7f}ü");
+    }
+
+    #[test]
+    fn literals() {
+        use crate::value::Value::*;
+
+        let json = JsonParser::parse_from_bytes(b"[true, false, null]").unwrap();
+
+        assert_eq!(json, Array(vec![Boolean(true), Boolean(false), Null]));
+    }
+
+    #[test]
+    fn invalid_literals() {
+        let error = tokenize_error("nope");
+        assert_eq!(error.kind(), &ErrorKind::InvalidLiteral);
+        assert_eq!(error.offset(), 1);
+
+        let error = tokenize_error("[truX]");
+        assert_eq!(error.kind(), &ErrorKind::InvalidLiteral);
+        assert_eq!(error.offset(), 4);
+
+        let error = tokenize_error("fals");
+        assert_eq!(error.kind(), &ErrorKind::InvalidLiteral);
+        assert_eq!(error.offset(), 4);
+
+        let error = tokenize_error("[nul\nl]");
+        assert_eq!(error.kind(), &ErrorKind::InvalidLiteral);
+        assert_eq!(error.line(), 1);
+        assert_eq!(error.column(), 5);
+
+        let error = tokenize_error("truex");
+        assert_eq!(error.kind(), &ErrorKind::TrailingCharacters);
+    }
+
+    #[test]
+    fn nul_byte_is_not_end_of_input() {
+        let error = tokenize_error("[1]\0");
+        assert_eq!(error.kind(), &ErrorKind::TrailingCharacters);
+
+        let error = tokenize_error("[1,\0 2]");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('\0'));
+        assert_eq!(error.offset(), 3);
+
+        let error = tokenize_error("{\0");
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedCharacter('\0'));
+    }
+
+    #[test]
+    fn number_representations() {
+        use crate::value::Number::*;
+        use crate::value::Value::*;
+
+        let json = JsonParser::parse_from_bytes(
+            b"[1e+5, -7, 18446744073709551615, 123456789012345678901234567890]",
+        )
+        .unwrap();
+
+        assert_eq!(
+            json,
+            Array(vec![
+                Number(F64(100_000.)),
+                Number(I64(-7)),
+                Number(U64(u64::MAX)),
+                Number(Raw("123456789012345678901234567890".to_string())),
+            ])
+        );
+    }
 }
@@ -1,9 +1,40 @@
-use std::collections::HashMap;
+use crate::map::Map;
+use crate::parser::BuildValue;
+use crate::pointer::{self, PointerError};
+use crate::writer::{self, Format};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use std::ops::Index;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// Returned by indexing into a value where there's nothing.
+static NULL: Value = Value::Null;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Number {
     I64(i64),
+    /// Positive integers that are too large for [`Number::I64`].
+    U64(u64),
     F64(f64),
+    /// The original text of a number that can't be represented by the other variants without
+    /// losing precision.
+    Raw(String),
+}
+
+impl Number {
+    /// The number as an `f64`, rounding it if it can't be represented exactly.
+    ///
+    /// Returns `None` if a [`Number::Raw`] is out of `f64` range.
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Number::F64(value) => Some(*value),
+            Number::I64(value) => Some(*value as f64),
+            Number::U64(value) => Some(*value as f64),
+            Number::Raw(value) => value.parse().ok().filter(|value: &f64| value.is_finite()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -12,80 +43,550 @@ pub enum Value {
     Number(Number),
     Boolean(bool),
     Array(Vec<Value>),
-    Object(HashMap<String, Value>),
+    Object(Map<String, Value>),
     Null,
 }
 
+impl Value {
+    /// Look up a value by JSON Pointer (RFC 6901), such as `/pairs/0/x0`, where `~1` stands for
+    /// `/` and `~0` for `~` in keys.
+    ///
+    /// Returns `None` if `pointer` is malformed or doesn't lead anywhere.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::parser::JsonParser;
+    /// use json_parser::value::{Number, Value};
+    ///
+    /// let value = JsonParser::parse_from_bytes(br#"{"pairs": [{"x0": 1.5}]}"#).unwrap();
+    ///
+    /// assert_eq!(value.pointer("/pairs/0/x0"), Some(&Value::Number(Number::F64(1.5))));
+    /// assert_eq!(value.pointer("/pairs/1/x0"), None);
+    /// ```
+    #[must_use]
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        pointer::resolve(self, pointer)
+    }
+
+    /// Look up a value by JSON Pointer for modification, like [`Value::pointer`].
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        pointer::resolve_mut(self, pointer)
+    }
+
+    /// Find every value matching `path`, a JSON Pointer in which `*` tokens match all elements
+    /// of an array or members of an object, in order.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PointerError::Malformed`] if `path` isn't a valid pointer, and with
+    /// [`PointerError::NotFound`] at the first location where any other token doesn't match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::parser::JsonParser;
+    ///
+    /// let value = JsonParser::parse_from_bytes(br#"{"pairs": [{"x0": 1.5}, {"x0": 2}]}"#).unwrap();
+    /// let x0 = value
+    ///     .query("/pairs/*/x0")
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(f64::try_from)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(x0, [1.5, 2.0]);
+    /// assert!(value.query("/pairs/*/y0").is_err());
+    /// ```
+    pub fn query(&self, path: &str) -> Result<Vec<&Value>, PointerError> {
+        pointer::query(self, path)
+    }
+
+    /// The boolean, or `None` if the value isn't one.
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        self.try_into().ok()
+    }
+
+    /// The number as an `i64`, or `None` if the value isn't a number that converts to one
+    /// without losing a fractional part or overflowing.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        self.try_into().ok()
+    }
+
+    /// The number as an `f64`, see [`Number::as_f64`], or `None` if the value isn't a number.
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        self.try_into().ok()
+    }
+
+    /// The string, or `None` if the value isn't one.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        self.try_into().ok()
+    }
+
+    /// The elements of the array, or `None` if the value isn't one.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        self.try_into().ok()
+    }
+
+    /// The members of the object, or `None` if the value isn't one.
+    #[must_use]
+    pub fn as_object(&self) -> Option<&Map<String, Value>> {
+        self.try_into().ok()
+    }
+
+    /// The JSON type of the value, as used in error messages.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Boolean(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    /// Write the value to `writer` as compact JSON.
+    ///
+    /// The writer isn't buffered, so wrap it in a [`std::io::BufWriter`] if it's a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::value::{Number, Value};
+    ///
+    /// let value = Value::Array(vec![Value::Number(Number::F64(0.1)), Value::Null]);
+    /// let mut output = Vec::new();
+    /// value.to_writer(&mut output).unwrap();
+    ///
+    /// assert_eq!(output, b"[0.1,null]");
+    /// ```
+    pub fn to_writer<W: Write>(&self, writer: W) -> io::Result<()> {
+        writer::write_value_io(writer, self, Format::Compact)
+    }
+
+    /// Write the value to `writer` as JSON with one element or member per line, indented by
+    /// `indent` spaces per level.
+    pub fn to_writer_pretty<W: Write>(&self, writer: W, indent: usize) -> io::Result<()> {
+        writer::write_value_io(writer, self, Format::Pretty { indent })
+    }
+
+    /// Write the value to `writer` as JSON laid out according to `format`.
+    pub fn to_writer_with<W: Write>(&self, writer: W, format: Format) -> io::Result<()> {
+        writer::write_value_io(writer, self, format)
+    }
+}
+
+/// Formats the value as compact JSON, or with `{:#}` as pretty JSON indented by 2 spaces.
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = if f.alternate() {
+            Format::Pretty { indent: 2 }
+        } else {
+            Format::Compact
+        };
+
+        writer::write_value(f, self, format)
+    }
+}
+
+impl BuildValue<'_> for Value {
+    type Key = String;
+
+    fn key(key: Cow<'_, str>) -> String {
+        key.into_owned()
+    }
+
+    fn from_string(string: Cow<'_, str>) -> Self {
+        Value::String(string.into_owned())
+    }
+
+    fn from_number(number: Number) -> Self {
+        Value::Number(number)
+    }
+
+    fn from_boolean(boolean: bool) -> Self {
+        Value::Boolean(boolean)
+    }
+
+    fn null() -> Self {
+        Value::Null
+    }
+
+    fn from_array(array: Vec<Self>) -> Self {
+        Value::Array(array)
+    }
+
+    fn from_object(object: Map<String, Value>) -> Self {
+        Value::Object(object)
+    }
+}
+
+/// Looks up a member of an object, or returns [`Value::Null`] if there isn't one.
+impl Index<&str> for Value {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(object) => object.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// Looks up an element of an array, or returns [`Value::Null`] if there isn't one.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::Array(array) => array.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// Error returned when a [`Value`] can't be converted to a Rust type with `TryFrom`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The value is of another JSON type, such as a string where a number was expected.
+    WrongType {
+        /// What was expected, such as `i64` or `an array`.
+        expected: &'static str,
+        /// The JSON type of the value, such as `a string`.
+        found: &'static str,
+    },
+    /// A number with a fractional part, converted to an integer type.
+    Fractional { expected: &'static str },
+    /// A number outside the range of the numeric type, such as `300` for a `u8`.
+    OutOfRange { expected: &'static str },
+}
+
+impl ConversionError {
+    pub(crate) fn wrong_type(expected: &'static str, found: &'static str) -> Self {
+        ConversionError::WrongType { expected, found }
+    }
+
+    /// What was expected, such as `i64` or `an array`.
+    #[must_use]
+    pub fn expected(&self) -> &'static str {
+        match self {
+            ConversionError::WrongType { expected, .. }
+            | ConversionError::Fractional { expected }
+            | ConversionError::OutOfRange { expected } => expected,
+        }
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::WrongType { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ConversionError::Fractional { expected } => {
+                write!(
+                    f,
+                    "expected {expected}, found a number with a fractional part"
+                )
+            }
+            ConversionError::OutOfRange { expected } => {
+                write!(f, "expected {expected}, found a number out of its range")
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
 impl TryFrom<&Value> for String {
-    type Error = ();
+    type Error = ConversionError;
 
-    fn try_from(value: &Value) -> Result<Self, ()> {
+    fn try_from(value: &Value) -> Result<Self, ConversionError> {
         match value {
-            Value::String(value) => Ok(value.clone()),
-            _ => Err(()),
+            Value::String(string) => Ok(string.clone()),
+            _ => Err(ConversionError::wrong_type("String", value.type_name())),
         }
     }
 }
 
-impl TryFrom<&Value> for i64 {
-    type Error = ();
+impl<'a> TryFrom<&'a Value> for &'a str {
+    type Error = ConversionError;
 
-    #[allow(clippy::cast_possible_truncation)]
-    fn try_from(value: &Value) -> Result<Self, ()> {
+    fn try_from(value: &'a Value) -> Result<Self, ConversionError> {
         match value {
-            Value::Number(value) => match value {
-                Number::I64(value) => Ok(*value),
-                Number::F64(value) => Ok(*value as i64),
-            },
-            _ => Err(()),
+            Value::String(string) => Ok(string),
+            _ => Err(ConversionError::wrong_type("&str", value.type_name())),
         }
     }
 }
 
+/// Integers convert if they are in range, and floats too if they have no fractional part.
+macro_rules! impl_try_from_integer {
+    ($($integer:ty),*) => {$(
+        impl TryFrom<&Number> for $integer {
+            type Error = ConversionError;
+
+            #[allow(
+                clippy::cast_lossless,
+                clippy::cast_possible_truncation,
+                clippy::cast_precision_loss,
+                clippy::cast_sign_loss
+            )]
+            fn try_from(number: &Number) -> Result<Self, ConversionError> {
+                let expected = stringify!($integer);
+                let out_of_range = ConversionError::OutOfRange { expected };
+
+                let float = match number {
+                    Number::I64(value) => return <$integer>::try_from(*value).or(Err(out_of_range)),
+                    Number::U64(value) => return <$integer>::try_from(*value).or(Err(out_of_range)),
+                    Number::F64(value) => *value,
+                    Number::Raw(text) if !text.contains(['.', 'e', 'E']) => {
+                        return text.parse().or(Err(out_of_range));
+                    }
+                    Number::Raw(_) => number.as_f64().ok_or(out_of_range.clone())?,
+                };
+
+                // The upper bound is a power of two, so adding one to the maximum rounds to it.
+                let range = <$integer>::MIN as f64..<$integer>::MAX as f64 + 1.0;
+
+                if float.is_finite() && float.fract() != 0.0 {
+                    Err(ConversionError::Fractional { expected })
+                } else if range.contains(&float) {
+                    Ok(float as $integer)
+                } else {
+                    Err(out_of_range)
+                }
+            }
+        }
+
+        impl TryFrom<&Value> for $integer {
+            type Error = ConversionError;
+
+            fn try_from(value: &Value) -> Result<Self, ConversionError> {
+                match value {
+                    Value::Number(number) => number.try_into(),
+                    _ => Err(ConversionError::wrong_type(stringify!($integer), value.type_name())),
+                }
+            }
+        }
+    )*};
+}
+
+impl_try_from_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Integers are rounded to the nearest `f64`, only [`Number::Raw`] numbers can be out of range.
+impl TryFrom<&Number> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(number: &Number) -> Result<Self, ConversionError> {
+        number
+            .as_f64()
+            .ok_or(ConversionError::OutOfRange { expected: "f64" })
+    }
+}
+
 impl TryFrom<&Value> for f64 {
-    type Error = ();
+    type Error = ConversionError;
 
-    fn try_from(value: &Value) -> Result<Self, ()> {
+    fn try_from(value: &Value) -> Result<Self, ConversionError> {
         match value {
-            Value::Number(value) => match value {
-                Number::F64(value) => Ok(*value),
-                Number::I64(value) => Ok(*value as f64),
-            },
-            _ => Err(()),
+            Value::Number(number) => number.try_into(),
+            _ => Err(ConversionError::wrong_type("f64", value.type_name())),
+        }
+    }
+}
+
+/// Numbers are rounded to the nearest `f32`, failing if they are beyond its largest values.
+impl TryFrom<&Number> for f32 {
+    type Error = ConversionError;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn try_from(number: &Number) -> Result<Self, ConversionError> {
+        let out_of_range = ConversionError::OutOfRange { expected: "f32" };
+        let float = number.as_f64().ok_or(out_of_range.clone())?;
+
+        if float.is_finite() && float.abs() > f64::from(f32::MAX) {
+            Err(out_of_range)
+        } else {
+            Ok(float as f32)
+        }
+    }
+}
+
+impl TryFrom<&Value> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(value: &Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Number(number) => number.try_into(),
+            _ => Err(ConversionError::wrong_type("f32", value.type_name())),
         }
     }
 }
 
 impl TryFrom<&Value> for bool {
-    type Error = ();
+    type Error = ConversionError;
 
-    fn try_from(value: &Value) -> Result<Self, ()> {
+    fn try_from(value: &Value) -> Result<Self, ConversionError> {
         match value {
-            Value::Boolean(value) => Ok(*value),
-            _ => Err(()),
+            Value::Boolean(boolean) => Ok(*boolean),
+            _ => Err(ConversionError::wrong_type("bool", value.type_name())),
         }
     }
 }
 
 impl<'a> TryFrom<&'a Value> for &'a Vec<Value> {
-    type Error = ();
+    type Error = ConversionError;
 
-    fn try_from(value: &'a Value) -> Result<Self, ()> {
+    fn try_from(value: &'a Value) -> Result<Self, ConversionError> {
         match value {
-            Value::Array(value) => Ok(value),
-            _ => Err(()),
+            Value::Array(array) => Ok(array),
+            _ => Err(ConversionError::wrong_type("an array", value.type_name())),
         }
     }
 }
 
-#[allow(clippy::implicit_hasher)]
-impl<'a> TryFrom<&'a Value> for &'a HashMap<String, Value> {
-    type Error = ();
+impl<'a> TryFrom<&'a Value> for &'a Map<String, Value> {
+    type Error = ConversionError;
 
-    fn try_from(value: &'a Value) -> Result<Self, ()> {
+    fn try_from(value: &'a Value) -> Result<Self, ConversionError> {
         match value {
-            Value::Object(value) => Ok(value),
-            _ => Err(()),
+            Value::Object(object) => Ok(object),
+            _ => Err(ConversionError::wrong_type("an object", value.type_name())),
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    /// Arbitrary values with finite numbers that parse back as the same variant, so writing and
+    /// parsing them again should give an equal value.
+    pub(crate) fn arbitrary_value() -> impl Strategy<Value = Value> {
+        let number = prop_oneof![
+            any::<i64>().prop_map(Number::I64),
+            (i64::MAX as u64 + 1..=u64::MAX).prop_map(Number::U64),
+            (prop::num::f64::NORMAL
+                | prop::num::f64::SUBNORMAL
+                | prop::num::f64::ZERO
+                | prop::num::f64::POSITIVE
+                | prop::num::f64::NEGATIVE)
+                .prop_map(Number::F64),
+        ];
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Boolean),
+            number.prop_map(Value::Number),
+            any::<String>().prop_map(Value::String),
+        ];
+
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..8).prop_map(Value::Array),
+                vec((any::<String>(), inner), 0..8)
+                    .prop_map(|members| Value::Object(members.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn number(text: &str) -> Value {
+        Value::Number(Number::Raw(text.to_string()))
+    }
+
+    #[test]
+    fn integer_conversions() {
+        assert_eq!(i64::try_from(&Value::Number(Number::I64(-3))), Ok(-3));
+        assert_eq!(u8::try_from(&Value::Number(Number::U64(255))), Ok(255));
+        assert_eq!(i32::try_from(&Value::Number(Number::F64(4.0))), Ok(4));
+        assert_eq!(
+            u128::try_from(&number("340282366920938463463374607431768211455")),
+            Ok(u128::MAX)
+        );
+        assert_eq!(i64::try_from(&number("-2e3")), Ok(-2000));
+
+        assert_eq!(
+            i64::try_from(&Value::Number(Number::F64(1.5))),
+            Err(ConversionError::Fractional { expected: "i64" })
+        );
+        assert_eq!(
+            u8::try_from(&Value::Number(Number::I64(256))),
+            Err(ConversionError::OutOfRange { expected: "u8" })
+        );
+        assert_eq!(
+            u32::try_from(&Value::Number(Number::I64(-1))),
+            Err(ConversionError::OutOfRange { expected: "u32" })
+        );
+        assert_eq!(
+            i64::try_from(&Value::Number(Number::F64(9_223_372_036_854_775_808.0))),
+            Err(ConversionError::OutOfRange { expected: "i64" })
+        );
+        assert_eq!(
+            i64::try_from(&number("18446744073709551616")),
+            Err(ConversionError::OutOfRange { expected: "i64" })
+        );
+        assert_eq!(
+            i64::try_from(&number("1e400")),
+            Err(ConversionError::OutOfRange { expected: "i64" })
+        );
+        assert_eq!(
+            i64::try_from(&Value::String("1".to_string())),
+            Err(ConversionError::WrongType {
+                expected: "i64",
+                found: "a string"
+            })
+        );
+    }
+
+    #[test]
+    fn float_conversions() {
+        assert_eq!(f64::try_from(&Value::Number(Number::I64(-3))), Ok(-3.0));
+        assert_eq!(f32::try_from(&Value::Number(Number::F64(0.5))), Ok(0.5));
+        assert_eq!(
+            f64::try_from(&number("1e400")),
+            Err(ConversionError::OutOfRange { expected: "f64" })
+        );
+        assert_eq!(
+            f32::try_from(&Value::Number(Number::F64(1e39))),
+            Err(ConversionError::OutOfRange { expected: "f32" })
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let string = Value::String("text".to_string());
+        let array = Value::Array(vec![Value::Null]);
+
+        assert_eq!(string.as_str(), Some("text"));
+        assert_eq!(array.as_array(), Some(&vec![Value::Null]));
+        assert_eq!(Value::Boolean(true).as_bool(), Some(true));
+        assert_eq!(Value::Number(Number::F64(2.0)).as_i64(), Some(2));
+        assert_eq!(Value::Number(Number::F64(2.5)).as_i64(), None);
+        assert_eq!(Value::Number(Number::I64(2)).as_f64(), Some(2.0));
+        assert_eq!(Value::Object(Map::new()).as_object(), Some(&Map::new()));
+
+        assert_eq!(string.as_f64(), None);
+        assert_eq!(array.as_object(), None);
+        assert_eq!(Value::Null.as_str(), None);
+
+        assert_eq!(
+            <&str>::try_from(&array).unwrap_err().to_string(),
+            "expected &str, found an array"
+        );
+        assert_eq!(
+            u8::try_from(&Value::Number(Number::I64(300)))
+                .unwrap_err()
+                .to_string(),
+            "expected u8, found a number out of its range"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use crate::value::{Number, Value};
+use std::fmt::{self, Write};
+use std::io;
+
+/// How a [`Value`] is laid out when it's serialized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Everything on one line, without any whitespace.
+    #[default]
+    Compact,
+    /// One element or member per line, nested ones indented by `indent` more spaces than their
+    /// parent, and a space after colons.
+    Pretty { indent: usize },
+}
+
+/// Write `value` to `writer` as JSON.
+///
+/// Strings are escaped as required by RFC 8259, plus `\b` and `\f`. Floats are written with the
+/// fewest digits that parse back to the same value and, as JSON can't represent them, infinities
+/// and NaN are written as `null`. Object members are written in order, including any duplicate
+/// keys.
+pub(crate) fn write_value<W: Write>(writer: &mut W, value: &Value, format: Format) -> fmt::Result {
+    Serializer::new(writer, format).value(value)
+}
+
+/// Write `value` to an [`io::Write`], reporting the first error it returned.
+pub(crate) fn write_value_io<W: io::Write>(
+    writer: W,
+    value: &Value,
+    format: Format,
+) -> io::Result<()> {
+    let mut writer = IoWriter::new(writer);
+
+    write_value(&mut writer, value, format).map_err(|fmt::Error| writer.take_error())
+}
+
+/// Writes values as JSON, keeping track of the layout.
+///
+/// With the `serde` feature, this is also the `serde::Serializer` behind `crate::ser`.
+pub(crate) struct Serializer<'w, W> {
+    writer: &'w mut W,
+    format: Format,
+    /// Number of containers the value being written is nested in.
+    depth: usize,
+}
+
+impl<'w, W: Write> Serializer<'w, W> {
+    pub(crate) fn new(writer: &'w mut W, format: Format) -> Self {
+        Self {
+            writer,
+            format,
+            depth: 0,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn writer(&mut self) -> &mut W {
+        self.writer
+    }
+
+    fn value(&mut self, value: &Value) -> fmt::Result {
+        match value {
+            Value::Null => self.writer.write_str("null"),
+            Value::Boolean(true) => self.writer.write_str("true"),
+            Value::Boolean(false) => self.writer.write_str("false"),
+            Value::Number(number) => write_number(self.writer, number),
+            Value::String(string) => write_string(self.writer, string),
+            Value::Array(array) => {
+                self.begin('[')?;
+
+                for (index, element) in array.iter().enumerate() {
+                    self.element(index)?;
+                    self.value(element)?;
+                }
+
+                self.end(']', array.len())
+            }
+            Value::Object(object) => {
+                self.begin('{')?;
+
+                for (index, (key, value)) in object.iter().enumerate() {
+                    self.element(index)?;
+                    write_string(self.writer, key)?;
+                    self.colon()?;
+                    self.value(value)?;
+                }
+
+                self.end('}', object.len())
+            }
+        }
+    }
+
+    /// Open an array or object with `bracket`.
+    pub(crate) fn begin(&mut self, bracket: char) -> fmt::Result {
+        self.depth += 1;
+        self.writer.write_char(bracket)
+    }
+
+    /// Start the element or member at `index` in the array or object being written.
+    pub(crate) fn element(&mut self, index: usize) -> fmt::Result {
+        if index > 0 {
+            self.writer.write_char(',')?;
+        }
+
+        self.new_line()
+    }
+
+    /// Separate an object member's key from its value.
+    pub(crate) fn colon(&mut self) -> fmt::Result {
+        match self.format {
+            Format::Compact => self.writer.write_char(':'),
+            Format::Pretty { .. } => self.writer.write_str(": "),
+        }
+    }
+
+    /// Close the array or object being written, which has `length` elements or members, with
+    /// `bracket`.
+    pub(crate) fn end(&mut self, bracket: char, length: usize) -> fmt::Result {
+        self.depth -= 1;
+
+        if length > 0 {
+            self.new_line()?;
+        }
+
+        self.writer.write_char(bracket)
+    }
+
+    /// Start a new line at the current depth, in pretty mode.
+    fn new_line(&mut self) -> fmt::Result {
+        if let Format::Pretty { indent } = self.format {
+            self.writer.write_char('\n')?;
+
+            for _ in 0..indent * self.depth {
+                self.writer.write_char(' ')?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn write_number<W: Write>(writer: &mut W, number: &Number) -> fmt::Result {
+    match number {
+        Number::I64(value) => write!(writer, "{value}"),
+        Number::U64(value) => write!(writer, "{value}"),
+        // Debug formatting is the shortest that round-trips, switches to an exponent for very
+        // large or small values, and keeps a fraction on whole numbers so they stay floats.
+        Number::F64(value) if value.is_finite() => write!(writer, "{value:?}"),
+        Number::F64(_) => writer.write_str("null"),
+        Number::Raw(text) => writer.write_str(text),
+    }
+}
+
+pub(crate) fn write_string<W: Write>(writer: &mut W, string: &str) -> fmt::Result {
+    writer.write_char('"')?;
+
+    // Write everything between escapes as a whole, which is all of it for most strings.
+    let mut start = 0;
+
+    for (index, byte) in string.bytes().enumerate() {
+        let escape = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0x00..=0x1f => "",
+            _ => continue,
+        };
+
+        writer.write_str(&string[start..index])?;
+
+        if escape.is_empty() {
+            write!(writer, "\\u{byte:04x}")?;
+        } else {
+            writer.write_str(escape)?;
+        }
+
+        start = index + 1;
+    }
+
+    writer.write_str(&string[start..])?;
+    writer.write_char('"')
+}
+
+/// Adapts an [`io::Write`] to [`fmt::Write`], keeping the error that [`fmt::Error`] can't carry.
+pub(crate) struct IoWriter<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W> IoWriter<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    /// The error behind the last [`fmt::Error`] returned.
+    pub(crate) fn take_error(&mut self) -> io::Error {
+        self.error
+            .take()
+            .unwrap_or_else(|| io::Error::other("failed to format JSON"))
+    }
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.writer.write_all(string.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::map::Map;
+    use crate::parser::JsonParser;
+    use crate::value::test::arbitrary_value;
+    use proptest::prelude::*;
+
+    fn round_trip(input: &str) {
+        let value = JsonParser::parse_from_bytes(input.as_bytes()).unwrap();
+
+        for output in [value.to_string(), format!("{value:#}")] {
+            assert_eq!(
+                JsonParser::parse_from_bytes(output.as_bytes()),
+                Ok(value.clone()),
+                "Round trip of {input:?} through {output:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let inputs = [
+            r#"{"pairs":[{"x0":95.26235434764715,"y0":-33.78221816487377,"x1":41.844453001935875,"y1":-78.10213222087448}]}"#,
+            r#"{"sample":"string \"sample\" é𝄞 ü \\ \/ \b\f\n\r\t \u0000\u001f","nullable":null}"#,
+            r#"[true, false, null, [], {}, [[]], {"a": {}}, ""]"#,
+            "[0, -0, 1, -1, 9223372036854775807, -9223372036854775808, 18446744073709551615]",
+            "[0.1, -0.0, 1.0, 1e300, 1e-300, 5e-324, 1.7976931348623157e308, 123456789.125, 1e16]",
+            "[123456789012345678901234567890, 1.00000000000000000000000000001]",
+            r#""top-level""#,
+            "42",
+        ];
+
+        for input in inputs {
+            round_trip(input);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_values_round_trip(value in arbitrary_value()) {
+            for output in [value.to_string(), format!("{value:#}")] {
+                prop_assert_eq!(JsonParser::parse_from_bytes(output.as_bytes()), Ok(value.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn compact() {
+        let value =
+            JsonParser::parse_from_bytes(br#" { "b" : [ 1 , { } , [ ] ] , "a" : "x" } "#).unwrap();
+
+        assert_eq!(value.to_string(), r#"{"b":[1,{},[]],"a":"x"}"#);
+    }
+
+    #[test]
+    fn pretty() {
+        let value =
+            JsonParser::parse_from_bytes(br#"{"b": [1, {"c": null}, []], "a": {}}"#).unwrap();
+
+        assert_eq!(
+            format!("{value:#}"),
+            "{\n  \"b\": [\n    1,\n    {\n      \"c\": null\n    },\n    []\n  ],\n  \"a\": {}\n}"
+        );
+
+        let mut output = Vec::new();
+        value.to_writer_pretty(&mut output, 4).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{value:#}").replace("  ", "    ")
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let value = Value::String("\"\\/\u{8}\u{c}\n\r\t\u{0}\u{1f}\u{7f}é𝄞".to_string());
+
+        assert_eq!(
+            value.to_string(),
+            "\"\\\"\\\\/\\b\\f\\n\\r\\t\\u0000\\u001f\u{7f}é𝄞\""
+        );
+    }
+
+    #[test]
+    fn floats_are_shortest() {
+        let float = |value: f64| Value::Number(Number::F64(value)).to_string();
+
+        assert_eq!(float(0.1), "0.1");
+        assert_eq!(float(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(float(1.0), "1.0");
+        assert_eq!(float(-0.0), "-0.0");
+        assert_eq!(float(1e300), "1e300");
+        assert_eq!(float(2.5e-8), "2.5e-8");
+        assert_eq!(float(f64::NAN), "null");
+        assert_eq!(float(f64::NEG_INFINITY), "null");
+    }
+
+    #[test]
+    fn io_errors_are_returned() {
+        struct Failing;
+
+        impl io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value = Value::Object(Map::from([("a".to_string(), Value::Null)]));
+        let error = value.to_writer(Failing).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}